@@ -0,0 +1,183 @@
+//! Native async operations of the device filesystem.
+//!
+//! Device nodes forward the operations to the async operations of the added
+//! nodes, whose reading and writing wait for them to be ready with their
+//! [`poll()`](axfs_vfs::VfsNodeOps::poll) unless they are native. Operations
+//! of directories complete on the first poll.
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::task::Waker;
+
+use axfs_vfs::{to_sync_node, AsyncVfsNodeOps, AsyncVfsNodeRef, AsyncVfsOps, FileSystemInfo};
+use axfs_vfs::{FallocateMode, LockManager, PageRef, PollEvents, RenameFlags, VfsAttrMask};
+use axfs_vfs::{VfsDirEntry, VfsFuture, VfsNodeAttr, VfsNodePerm, VfsNodeRef, VfsNodeType};
+use axfs_vfs::{VfsOps, VfsResult, XattrFlags};
+
+use crate::node::DeviceNode;
+use crate::{DeviceFileSystem, DirNode};
+
+impl AsyncVfsOps for DeviceFileSystem {
+    fn mount<'a>(&'a self, path: &'a str, mount_point: AsyncVfsNodeRef) -> VfsFuture<'a> {
+        let mount_point = to_sync_node(mount_point);
+        Box::pin(async move { VfsOps::mount(self, path, mount_point) })
+    }
+
+    fn statfs(&self) -> VfsFuture<'_, FileSystemInfo> {
+        Box::pin(async move { VfsOps::statfs(self) })
+    }
+
+    fn root_dir(&self) -> AsyncVfsNodeRef {
+        self.root.clone()
+    }
+
+    fn dev_id(&self) -> u64 {
+        VfsOps::dev_id(self)
+    }
+}
+
+impl AsyncVfsNodeOps for DirNode {
+    axfs_vfs::impl_vfs_async_from_sync! {}
+}
+
+impl AsyncVfsNodeOps for DeviceNode {
+    fn open(&self) -> VfsFuture<'_> {
+        self.async_inner.open()
+    }
+
+    fn release(&self) -> VfsFuture<'_> {
+        self.async_inner.release()
+    }
+
+    fn get_attr(&self) -> VfsFuture<'_, VfsNodeAttr> {
+        Box::pin(async move {
+            let mut attr = self.async_inner.get_attr().await?;
+            attr.set_dev(self.dev);
+            attr.set_ino(self.ino);
+            Ok(attr)
+        })
+    }
+
+    fn set_attr<'a>(&'a self, attr: &'a VfsNodeAttr, mask: VfsAttrMask) -> VfsFuture<'a> {
+        self.async_inner.set_attr(attr, mask)
+    }
+
+    fn get_xattr<'a>(&'a self, name: &'a str, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        self.async_inner.get_xattr(name, buf)
+    }
+
+    fn set_xattr<'a>(&'a self, name: &'a str, value: &'a [u8], flags: XattrFlags) -> VfsFuture<'a> {
+        self.async_inner.set_xattr(name, value, flags)
+    }
+
+    fn list_xattr<'a>(&'a self, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        self.async_inner.list_xattr(buf)
+    }
+
+    fn remove_xattr<'a>(&'a self, name: &'a str) -> VfsFuture<'a> {
+        self.async_inner.remove_xattr(name)
+    }
+
+    fn lock_manager(&self) -> Option<&LockManager> {
+        axfs_vfs::VfsNodeOps::lock_manager(self)
+    }
+
+    fn read_at<'a>(&'a self, offset: u64, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        self.async_inner.read_at(offset, buf)
+    }
+
+    fn write_at<'a>(&'a self, offset: u64, buf: &'a [u8]) -> VfsFuture<'a, usize> {
+        self.async_inner.write_at(offset, buf)
+    }
+
+    fn fsync(&self) -> VfsFuture<'_> {
+        self.async_inner.fsync()
+    }
+
+    fn truncate(&self, size: u64) -> VfsFuture<'_> {
+        self.async_inner.truncate(size)
+    }
+
+    fn fallocate(&self, mode: FallocateMode, offset: u64, len: u64) -> VfsFuture<'_> {
+        self.async_inner.fallocate(mode, offset, len)
+    }
+
+    fn ioctl<'a>(&'a self, cmd: u32, arg: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        self.async_inner.ioctl(cmd, arg)
+    }
+
+    fn poll(&self, events: PollEvents, waker: Option<&Waker>) -> VfsResult<PollEvents> {
+        self.async_inner.poll(events, waker)
+    }
+
+    fn get_pages(&self, offset: u64, len: u64) -> VfsFuture<'_, Vec<PageRef>> {
+        self.async_inner.get_pages(offset, len)
+    }
+
+    fn writeback_pages(&self, offset: u64, len: u64) -> VfsFuture<'_> {
+        self.async_inner.writeback_pages(offset, len)
+    }
+
+    fn readlink<'a>(&'a self, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        self.async_inner.readlink(buf)
+    }
+
+    fn parent(&self) -> Option<AsyncVfsNodeRef> {
+        self.async_inner.parent()
+    }
+
+    fn lookup<'a>(self: Arc<Self>, path: &'a str) -> VfsFuture<'a, AsyncVfsNodeRef> {
+        self.async_inner.clone().lookup(path)
+    }
+
+    fn lookup_nofollow<'a>(self: Arc<Self>, path: &'a str) -> VfsFuture<'a, AsyncVfsNodeRef> {
+        self.async_inner.clone().lookup_nofollow(path)
+    }
+
+    fn create<'a>(&'a self, path: &'a str, ty: VfsNodeType) -> VfsFuture<'a> {
+        self.async_inner.create(path, ty)
+    }
+
+    fn mknod<'a>(
+        &'a self,
+        path: &'a str,
+        ty: VfsNodeType,
+        perm: VfsNodePerm,
+        rdev: u64,
+    ) -> VfsFuture<'a> {
+        self.async_inner.mknod(path, ty, perm, rdev)
+    }
+
+    fn symlink<'a>(&'a self, path: &'a str, target: &'a str) -> VfsFuture<'a> {
+        self.async_inner.symlink(path, target)
+    }
+
+    fn link<'a>(&'a self, path: &'a str, node: &'a AsyncVfsNodeRef) -> VfsFuture<'a> {
+        self.async_inner.link(path, node)
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a> {
+        self.async_inner.remove(path)
+    }
+
+    fn read_dir<'a>(&'a self, cookie: u64, dirents: &'a mut [VfsDirEntry]) -> VfsFuture<'a, usize> {
+        self.async_inner.read_dir(cookie, dirents)
+    }
+
+    fn rename<'a>(&'a self, src_path: &'a str, dst_path: &'a str) -> VfsFuture<'a> {
+        self.async_inner.rename(src_path, dst_path)
+    }
+
+    fn rename_with_flags<'a>(
+        &'a self,
+        src_path: &'a str,
+        dst_path: &'a str,
+        flags: RenameFlags,
+    ) -> VfsFuture<'a> {
+        self.async_inner
+            .rename_with_flags(src_path, dst_path, flags)
+    }
+
+    fn into_sync(self: Arc<Self>) -> Option<VfsNodeRef> {
+        Some(self)
+    }
+}
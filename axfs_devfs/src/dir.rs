@@ -1,23 +1,76 @@
 use alloc::collections::BTreeMap;
 use alloc::sync::{Arc, Weak};
+use axfs_vfs::{RenameFlags, VfsDirOps, VfsError, VfsNodePerm, VfsResult};
 use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
-use axfs_vfs::{VfsError, VfsResult};
 use spin::RwLock;
 
+use crate::node::{DeviceNode, FsContext};
+
+/// The `read_dir` cookie of the first entry after `.` and `..`.
+const FIRST_COOKIE: u64 = 2;
+
+/// Entries of a directory.
+///
+/// Each entry is assigned an increasing sequence number when added, which is
+/// used as its `read_dir` cookie, so a listing is not affected by entries
+/// added in between.
+struct Children {
+    next_seq: u64,
+    by_name: BTreeMap<&'static str, (u64, VfsNodeRef)>,
+    by_seq: BTreeMap<u64, &'static str>,
+}
+
+impl Children {
+    const fn new() -> Self {
+        Self {
+            next_seq: FIRST_COOKIE,
+            by_name: BTreeMap::new(),
+            by_seq: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&VfsNodeRef> {
+        self.by_name.get(name).map(|(_, node)| node)
+    }
+
+    fn insert(&mut self, name: &'static str, node: VfsNodeRef) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.by_seq.insert(seq, name);
+        if let Some((old_seq, _)) = self.by_name.insert(name, (seq, node)) {
+            self.by_seq.remove(&old_seq);
+        }
+    }
+
+    /// Returns the entries from the cookie `start`, with the cookie of each
+    /// entry.
+    fn range(&self, start: u64) -> impl Iterator<Item = (u64, &'static str, &VfsNodeRef)> {
+        self.by_seq
+            .range(start..)
+            .map(|(&seq, &name)| (seq, name, &self.by_name[name].1))
+    }
+}
+
 /// The directory node in the device filesystem.
 ///
 /// It implements [`axfs_vfs::VfsNodeOps`].
 pub struct DirNode {
+    this: Weak<DirNode>,
+    pub(super) fs: Arc<FsContext>,
+    ino: u64,
     parent: RwLock<Weak<dyn VfsNodeOps>>,
-    children: RwLock<BTreeMap<&'static str, VfsNodeRef>>,
+    children: RwLock<Children>,
 }
 
 impl DirNode {
-    pub(super) fn new(parent: Option<&VfsNodeRef>) -> Arc<Self> {
+    pub(super) fn new(parent: Option<&VfsNodeRef>, fs: &Arc<FsContext>) -> Arc<Self> {
         let parent = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
-        Arc::new(Self {
+        Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            fs: fs.clone(),
+            ino: fs.alloc_ino(),
             parent: RwLock::new(parent),
-            children: RwLock::new(BTreeMap::new()),
+            children: RwLock::new(Children::new()),
         })
     }
 
@@ -28,111 +81,132 @@ impl DirNode {
     /// Create a subdirectory at this directory.
     pub fn mkdir(self: &Arc<Self>, name: &'static str) -> Arc<Self> {
         let parent = self.clone() as VfsNodeRef;
-        let node = Self::new(Some(&parent));
+        let node = Self::new(Some(&parent), &self.fs);
         self.children.write().insert(name, node.clone());
         node
     }
 
     /// Add a node to this directory.
+    ///
+    /// The node is assigned a new inode number in this filesystem, and is
+    /// looked up as a [`DeviceNode`] wrapping it.
     pub fn add(&self, name: &'static str, node: VfsNodeRef) {
+        let node = Arc::new(DeviceNode::new(&self.fs, node));
         self.children.write().insert(name, node);
     }
+
+    fn parent_ino(&self) -> u64 {
+        self.parent()
+            .and_then(|parent| parent.get_attr().ok())
+            .map_or(self.ino, |attr| attr.ino())
+    }
 }
 
 impl VfsNodeOps for DirNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_dir(4096, 0))
+        let mut attr = VfsNodeAttr::new_dir(4096, 0);
+        attr.set_dev(self.fs.dev());
+        attr.set_ino(self.ino);
+        Ok(attr)
     }
 
     fn parent(&self) -> Option<VfsNodeRef> {
         self.parent.read().upgrade()
     }
 
-    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
-        let (name, rest) = split_path(path);
-        let node = match name {
-            "" | "." => Ok(self.clone() as VfsNodeRef),
-            ".." => self.parent().ok_or(VfsError::NotFound),
-            _ => self
-                .children
-                .read()
-                .get(name)
-                .cloned()
-                .ok_or(VfsError::NotFound),
-        }?;
-
-        if let Some(rest) = rest {
-            node.lookup(rest)
-        } else {
-            Ok(node)
-        }
-    }
-
-    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+    fn read_dir(&self, cookie: u64, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
         let children = self.children.read();
-        let mut children = children.iter().skip(start_idx.max(2) - 2);
+        let mut children = children.range(cookie.max(FIRST_COOKIE));
+        let mut cookie = cookie;
         for (i, ent) in dirents.iter_mut().enumerate() {
-            match i + start_idx {
-                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
-                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+            match cookie {
+                0 => {
+                    *ent = VfsDirEntry::new(".", VfsNodeType::Dir);
+                    ent.set_ino(self.ino);
+                    cookie = 1;
+                }
+                1 => {
+                    *ent = VfsDirEntry::new("..", VfsNodeType::Dir);
+                    ent.set_ino(self.parent_ino());
+                    cookie = FIRST_COOKIE;
+                }
                 _ => {
-                    if let Some((name, node)) = children.next() {
-                        *ent = VfsDirEntry::new(name, node.get_attr().unwrap().file_type());
+                    if let Some((seq, name, node)) = children.next() {
+                        let attr = node.get_attr().unwrap();
+                        *ent = VfsDirEntry::new(name, attr.file_type());
+                        ent.set_ino(attr.ino());
+                        cookie = seq + 1;
                     } else {
                         return Ok(i);
                     }
                 }
             }
+            ent.set_offset(cookie);
         }
         Ok(dirents.len())
     }
 
-    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
-        log::debug!("create {ty:?} at devfs: {path}");
-        let (name, rest) = split_path(path);
-        if let Some(rest) = rest {
-            match name {
-                "" | "." => self.create(rest, ty),
-                ".." => self.parent().ok_or(VfsError::NotFound)?.create(rest, ty),
-                _ => self
-                    .children
-                    .read()
-                    .get(name)
-                    .ok_or(VfsError::NotFound)?
-                    .create(rest, ty),
-            }
-        } else if name.is_empty() || name == "." || name == ".." {
-            Ok(()) // already exists
-        } else {
-            Err(VfsError::PermissionDenied) // do not support to create nodes dynamically
-        }
-    }
-
-    fn remove(&self, path: &str) -> VfsResult {
-        log::debug!("remove at devfs: {path}");
-        let (name, rest) = split_path(path);
-        if let Some(rest) = rest {
-            match name {
-                "" | "." => self.remove(rest),
-                ".." => self.parent().ok_or(VfsError::NotFound)?.remove(rest),
-                _ => self
-                    .children
-                    .read()
-                    .get(name)
-                    .ok_or(VfsError::NotFound)?
-                    .remove(rest),
-            }
-        } else {
-            Err(VfsError::PermissionDenied) // do not support to remove nodes dynamically
-        }
+    axfs_vfs::impl_vfs_dir_paths! {}
+    #[cfg(feature = "async")]
+    fn into_async(self: Arc<Self>) -> Option<axfs_vfs::AsyncVfsNodeRef> {
+        Some(self)
     }
 
     axfs_vfs::impl_vfs_dir_default! {}
 }
 
-fn split_path(path: &str) -> (&str, Option<&str>) {
-    let trimmed_path = path.trim_start_matches('/');
-    trimmed_path.find('/').map_or((trimmed_path, None), |n| {
-        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
-    })
+impl VfsDirOps for DirNode {
+    fn node_ref(&self) -> VfsNodeRef {
+        self.this.upgrade().unwrap()
+    }
+
+    fn lookup_entry(&self, name: &str) -> VfsResult<VfsNodeRef> {
+        self.children
+            .read()
+            .get(name)
+            .cloned()
+            .ok_or(VfsError::NotFound)
+    }
+
+    fn create_entry(&self, name: &str, ty: VfsNodeType) -> VfsResult {
+        log::debug!("create {ty:?} at devfs: {name}");
+        Err(VfsError::PermissionDenied) // do not support to create nodes dynamically
+    }
+
+    fn mknod_entry(
+        &self,
+        name: &str,
+        ty: VfsNodeType,
+        _perm: VfsNodePerm,
+        _rdev: u64,
+    ) -> VfsResult {
+        log::debug!("mknod {ty:?} at devfs: {name}");
+        Err(VfsError::PermissionDenied) // do not support to create nodes dynamically
+    }
+
+    fn symlink_entry(&self, name: &str, _target: &str) -> VfsResult {
+        log::debug!("symlink at devfs: {name}");
+        Err(VfsError::PermissionDenied) // do not support to create nodes dynamically
+    }
+
+    fn link_entry(&self, name: &str, _node: &VfsNodeRef) -> VfsResult {
+        log::debug!("link at devfs: {name}");
+        Err(VfsError::PermissionDenied) // do not support to create nodes dynamically
+    }
+
+    fn remove_entry(&self, name: &str) -> VfsResult {
+        log::debug!("remove at devfs: {name}");
+        Err(VfsError::PermissionDenied) // do not support to remove nodes dynamically
+    }
+
+    fn rename_entry(
+        &self,
+        src_name: &str,
+        _dst_dir: &VfsNodeRef,
+        dst_name: &str,
+        _flags: RenameFlags,
+    ) -> VfsResult {
+        log::debug!("rename at devfs: {src_name} -> {dst_name}");
+        Err(VfsError::PermissionDenied) // do not support to move nodes dynamically
+    }
 }
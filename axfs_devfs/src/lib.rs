@@ -6,8 +6,12 @@
 
 extern crate alloc;
 
+#[cfg(feature = "async")]
+mod async_ops;
 mod dir;
+mod node;
 mod null;
+mod symlink;
 mod urandom;
 mod zero;
 
@@ -15,14 +19,18 @@ mod zero;
 mod tests;
 
 pub use self::dir::DirNode;
+pub use self::node::DeviceNode;
 pub use self::null::NullDev;
+pub use self::symlink::SymlinkNode;
 pub use self::urandom::UrandomDev;
 pub use self::zero::ZeroDev;
 
 use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodeRef, VfsOps, VfsResult};
 use spin::once::Once;
 
+use self::node::FsContext;
+
 /// A device filesystem that implements [`axfs_vfs::VfsOps`].
 pub struct DeviceFileSystem {
     parent: Once<VfsNodeRef>,
@@ -34,7 +42,7 @@ impl DeviceFileSystem {
     pub fn new() -> Self {
         Self {
             parent: Once::new(),
-            root: DirNode::new(None),
+            root: DirNode::new(None, &FsContext::new()),
         }
     }
 
@@ -46,6 +54,7 @@ impl DeviceFileSystem {
     /// Add a node to the root directory.
     ///
     /// The node must implement [`axfs_vfs::VfsNodeOps`], and be wrapped in [`Arc`].
+    /// It is looked up as a [`DeviceNode`] wrapping it.
     pub fn add(&self, name: &'static str, node: VfsNodeRef) {
         self.root.add(name, node);
     }
@@ -61,9 +70,17 @@ impl VfsOps for DeviceFileSystem {
         Ok(())
     }
 
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        Ok(self.root.fs.statfs())
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         self.root.clone()
     }
+
+    fn dev_id(&self) -> u64 {
+        self.root.fs.dev()
+    }
 }
 
 impl Default for DeviceFileSystem {
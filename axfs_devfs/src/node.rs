@@ -0,0 +1,261 @@
+use alloc::{sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::Waker;
+
+use axfs_vfs::{alloc_anon_dev, FallocateMode, FileSystemInfo, LockManager};
+use axfs_vfs::{Credentials, PageRef, PollEvents, VfsAttrMask, VfsDirEntry};
+use axfs_vfs::{RenameFlags, XattrFlags, NAME_MAX};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType, VfsResult};
+
+/// `TMPFS_MAGIC` in Linux, which is also used by devtmpfs.
+const TMPFS_MAGIC: u64 = 0x0102_1994;
+
+/// States shared by all nodes in the same device filesystem.
+pub(crate) struct FsContext {
+    dev: u64,
+    next_ino: AtomicU64,
+}
+
+impl FsContext {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            dev: alloc_anon_dev(),
+            next_ino: AtomicU64::new(1),
+        })
+    }
+
+    /// Returns the device ID of the filesystem.
+    pub fn dev(&self) -> u64 {
+        self.dev
+    }
+
+    /// Allocates a new inode number.
+    pub fn alloc_ino(&self) -> u64 {
+        self.next_ino.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Returns the attributes of the filesystem.
+    ///
+    /// Devices occupy no blocks, and nodes are never removed, so the number
+    /// of inodes is the number of allocated inode numbers.
+    pub fn statfs(&self) -> FileSystemInfo {
+        let mut info = FileSystemInfo::default();
+        info.fs_type = TMPFS_MAGIC;
+        info.block_size = 4096;
+        info.files = self.next_ino.load(Ordering::Relaxed) - 1;
+        info.fsid = self.dev;
+        info.name_max = NAME_MAX as _;
+        info
+    }
+}
+
+/// A node added by users to the device filesystem.
+///
+/// Devices such as [`NullDev`](crate::NullDev) do not know which filesystem
+/// they are in, so this wrapper assigns them an inode number and forwards all
+/// other operations to the inner node.
+///
+/// Lookups return the wrapper rather than the node given to
+/// [`DirNode::add`](crate::DirNode::add), so [`Arc::ptr_eq`] between the two
+/// fails. [`as_any()`](VfsNodeOps::as_any) is forwarded to the added node,
+/// so downcasting the result of a lookup to the device type still works.
+pub struct DeviceNode {
+    pub(crate) dev: u64,
+    pub(crate) ino: u64,
+    inner: VfsNodeRef,
+    /// The async operations of `inner`, native or from an adapter.
+    #[cfg(feature = "async")]
+    pub(crate) async_inner: axfs_vfs::AsyncVfsNodeRef,
+    locks: LockManager,
+}
+
+impl DeviceNode {
+    pub(crate) fn new(fs: &FsContext, inner: VfsNodeRef) -> Self {
+        Self {
+            dev: fs.dev(),
+            ino: fs.alloc_ino(),
+            #[cfg(feature = "async")]
+            async_inner: axfs_vfs::to_async_node(inner.clone()),
+            inner,
+            locks: LockManager::new(),
+        }
+    }
+
+    /// Returns the node that was added to the filesystem.
+    pub fn inner(&self) -> &VfsNodeRef {
+        &self.inner
+    }
+}
+
+impl VfsNodeOps for DeviceNode {
+    fn open(&self) -> VfsResult {
+        self.inner.open()
+    }
+
+    fn release(&self) -> VfsResult {
+        self.inner.release()
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let mut attr = self.inner.get_attr()?;
+        attr.set_dev(self.dev);
+        attr.set_ino(self.ino);
+        Ok(attr)
+    }
+
+    fn set_attr(&self, attr: &VfsNodeAttr, mask: VfsAttrMask) -> VfsResult {
+        self.inner.set_attr(attr, mask)
+    }
+
+    fn get_xattr(&self, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        self.inner.get_xattr(name, buf)
+    }
+
+    fn set_xattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> VfsResult {
+        self.inner.set_xattr(name, value, flags)
+    }
+
+    fn list_xattr(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        self.inner.list_xattr(buf)
+    }
+
+    fn remove_xattr(&self, name: &str) -> VfsResult {
+        self.inner.remove_xattr(name)
+    }
+
+    fn lock_manager(&self) -> Option<&LockManager> {
+        self.inner.lock_manager().or(Some(&self.locks))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.inner.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.inner.write_at(offset, buf)
+    }
+
+    fn read_vectored_at(&self, offset: u64, bufs: &mut [&mut [u8]]) -> VfsResult<usize> {
+        self.inner.read_vectored_at(offset, bufs)
+    }
+
+    fn write_vectored_at(&self, offset: u64, bufs: &[&[u8]]) -> VfsResult<usize> {
+        self.inner.write_vectored_at(offset, bufs)
+    }
+
+    fn append(&self, bufs: &[&[u8]]) -> VfsResult<(u64, usize)> {
+        self.inner.append(bufs)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.inner.fsync()
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        self.inner.truncate(size)
+    }
+
+    fn copy_range(
+        &self,
+        offset: u64,
+        src: &VfsNodeRef,
+        src_offset: u64,
+        len: u64,
+    ) -> VfsResult<u64> {
+        self.inner.copy_range(offset, src, src_offset, len)
+    }
+
+    fn fallocate(&self, mode: FallocateMode, offset: u64, len: u64) -> VfsResult {
+        self.inner.fallocate(mode, offset, len)
+    }
+
+    fn seek_data(&self, offset: u64) -> VfsResult<Option<u64>> {
+        self.inner.seek_data(offset)
+    }
+
+    fn seek_hole(&self, offset: u64) -> VfsResult<Option<u64>> {
+        self.inner.seek_hole(offset)
+    }
+
+    fn ioctl(&self, cmd: u32, arg: &mut [u8]) -> VfsResult<usize> {
+        self.inner.ioctl(cmd, arg)
+    }
+
+    fn poll(&self, events: PollEvents, waker: Option<&Waker>) -> VfsResult<PollEvents> {
+        self.inner.poll(events, waker)
+    }
+
+    fn get_pages(&self, offset: u64, len: u64) -> VfsResult<Vec<PageRef>> {
+        self.inner.get_pages(offset, len)
+    }
+
+    fn writeback_pages(&self, offset: u64, len: u64) -> VfsResult {
+        self.inner.writeback_pages(offset, len)
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        self.inner.readlink(buf)
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.inner.parent()
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        self.inner.clone().lookup(path)
+    }
+
+    fn lookup_nofollow(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        self.inner.clone().lookup_nofollow(path)
+    }
+
+    fn lookup_as(
+        self: Arc<Self>,
+        path: &str,
+        cred: &Credentials,
+        follow: bool,
+    ) -> VfsResult<VfsNodeRef> {
+        self.inner.clone().lookup_as(path, cred, follow)
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        self.inner.create(path, ty)
+    }
+
+    fn mknod(&self, path: &str, ty: VfsNodeType, perm: VfsNodePerm, rdev: u64) -> VfsResult {
+        self.inner.mknod(path, ty, perm, rdev)
+    }
+
+    fn symlink(&self, path: &str, target: &str) -> VfsResult {
+        self.inner.symlink(path, target)
+    }
+
+    fn link(&self, path: &str, node: &VfsNodeRef) -> VfsResult {
+        self.inner.link(path, node)
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        self.inner.remove(path)
+    }
+
+    fn read_dir(&self, cookie: u64, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        self.inner.read_dir(cookie, dirents)
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        self.inner.rename(src_path, dst_path)
+    }
+
+    fn rename_with_flags(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
+        self.inner.rename_with_flags(src_path, dst_path, flags)
+    }
+
+    #[cfg(feature = "async")]
+    fn into_async(self: Arc<Self>) -> Option<axfs_vfs::AsyncVfsNodeRef> {
+        Some(self)
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self.inner.as_any()
+    }
+}
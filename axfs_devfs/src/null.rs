@@ -1,4 +1,4 @@
-use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use axfs_vfs::{makedev, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
 
 /// A null device behaves like `/dev/null`.
 ///
@@ -7,12 +7,9 @@ pub struct NullDev;
 
 impl VfsNodeOps for NullDev {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new(
-            VfsNodePerm::default_file(),
-            VfsNodeType::CharDevice,
-            0,
-            0,
-        ))
+        let mut attr = VfsNodeAttr::new(VfsNodePerm::default_file(), VfsNodeType::CharDevice, 0, 0);
+        attr.set_rdev(makedev(1, 3));
+        Ok(attr)
     }
 
     fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
@@ -23,6 +20,14 @@ impl VfsNodeOps for NullDev {
         Ok(buf.len())
     }
 
+    fn read_vectored_at(&self, _offset: u64, _bufs: &mut [&mut [u8]]) -> VfsResult<usize> {
+        Ok(0)
+    }
+
+    fn write_vectored_at(&self, _offset: u64, bufs: &[&[u8]]) -> VfsResult<usize> {
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+    }
+
     fn truncate(&self, _size: u64) -> VfsResult {
         Ok(())
     }
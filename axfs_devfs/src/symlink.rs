@@ -0,0 +1,34 @@
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+
+/// A symbolic link in the device filesystem, such as `/dev/stdin`.
+///
+/// It points to a fixed target path.
+pub struct SymlinkNode {
+    target: &'static str,
+}
+
+impl SymlinkNode {
+    /// Create a new symbolic link that points to `target`.
+    pub const fn new(target: &'static str) -> Self {
+        Self { target }
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_symlink(),
+            VfsNodeType::SymLink,
+            self.target.len() as _,
+            0,
+        ))
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let len = self.target.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.target.as_bytes()[..len]);
+        Ok(len)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
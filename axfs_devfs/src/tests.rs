@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+use axfs_vfs::{VfsError, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsResult};
 
 use crate::*;
 
@@ -113,3 +113,561 @@ fn test_devfs() {
     test_devfs_ops(&devfs).unwrap();
     test_get_parent(&devfs).unwrap();
 }
+
+#[test]
+fn test_dir_ops() {
+    use axfs_vfs::{RenameFlags, VfsDirOps, VfsNodePerm};
+
+    // .
+    // ├── foo
+    // │   └── f1 (null)
+    // └── null
+
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    let dir_foo = devfs.mkdir("foo");
+    dir_foo.add("f1", Arc::new(NullDev));
+    let root = devfs.root_dir();
+    let null = root.clone().lookup("null").unwrap();
+    let perm = VfsNodePerm::default_file();
+
+    // single names, nodes can neither be added nor removed
+    assert_eq!(dir_foo.lookup_entry("f2").err(), Some(VfsError::NotFound));
+    assert!(dir_foo.lookup_entry("f1").is_ok());
+    for res in [
+        dir_foo.create_entry("f2", VfsNodeType::File),
+        dir_foo.mknod_entry("f2", VfsNodeType::Fifo, perm, 0),
+        dir_foo.symlink_entry("f2", "f1"),
+        dir_foo.link_entry("f2", &null),
+        dir_foo.remove_entry("f1"),
+        dir_foo.rename_entry("f1", &root, "f2", RenameFlags::empty()),
+    ] {
+        assert_eq!(res, Err(VfsError::PermissionDenied));
+    }
+
+    // paths are walked to the directory of the last component
+    assert_eq!(root.create("foo/./", VfsNodeType::Dir), Ok(()));
+    assert_eq!(
+        root.create("foo/f2", VfsNodeType::File),
+        Err(VfsError::PermissionDenied)
+    );
+    assert_eq!(
+        root.create("null/f2", VfsNodeType::File),
+        Err(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.mknod("foo/f2", VfsNodeType::Fifo, perm, 0),
+        Err(VfsError::PermissionDenied)
+    );
+    assert_eq!(root.link("foo/f2", &null), Err(VfsError::PermissionDenied));
+    assert_eq!(root.remove("foo/f1"), Err(VfsError::PermissionDenied));
+    assert_eq!(root.remove("foo/.."), Err(VfsError::InvalidInput));
+    assert_eq!(
+        root.rename("null", "foo/null"),
+        Err(VfsError::PermissionDenied)
+    );
+    assert_eq!(root.rename("foo/f1", "."), Err(VfsError::InvalidInput));
+}
+
+#[test]
+fn test_symlink() {
+    // .
+    // ├── foo
+    // │   ├── f1 -> ../null
+    // │   └── f2 -> /bar
+    // ├── bar -> foo
+    // └── null
+
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    devfs.add("bar", Arc::new(SymlinkNode::new("foo")));
+    let dir_foo = devfs.mkdir("foo");
+    dir_foo.add("f1", Arc::new(SymlinkNode::new("../null")));
+    dir_foo.add("f2", Arc::new(SymlinkNode::new("/bar")));
+
+    let root = devfs.root_dir();
+    let null = root.clone().lookup("null").unwrap();
+    let foo = root.clone().lookup("foo").unwrap();
+    let f1 = root.clone().lookup_nofollow("foo/f1").unwrap();
+    assert_eq!(f1.get_attr().unwrap().file_type(), VfsNodeType::SymLink);
+    let mut buf = [0; 16];
+    assert_eq!(f1.readlink(&mut buf).unwrap(), 7);
+    assert_eq!(&buf[..7], b"../null");
+
+    assert!(Arc::ptr_eq(&root.clone().lookup("foo/f1").unwrap(), &null));
+    assert!(Arc::ptr_eq(&root.clone().lookup("bar/f1").unwrap(), &null));
+    assert!(Arc::ptr_eq(&root.clone().lookup("foo/f2").unwrap(), &foo));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("bar/f2/f2/f1").unwrap(),
+        &null
+    ));
+    assert!(root
+        .clone()
+        .lookup_nofollow("bar")
+        .unwrap()
+        .get_attr()
+        .unwrap()
+        .is_symlink());
+    assert_eq!(
+        root.symlink("baz", "foo").err(),
+        Some(VfsError::PermissionDenied)
+    );
+}
+
+#[test]
+fn test_inode_number() {
+    use axfs_vfs::VfsDirEntry;
+
+    let devfs = DeviceFileSystem::new();
+    let null = Arc::new(NullDev);
+    devfs.add("null", null.clone());
+    devfs.add("null2", null);
+    devfs.add("zero", Arc::new(ZeroDev));
+    devfs.mkdir("foo").add("f1", Arc::new(ZeroDev));
+
+    let root = devfs.root_dir();
+    let ino = |path| {
+        let attr = root.clone().lookup(path).unwrap().get_attr().unwrap();
+        assert_eq!(attr.dev(), devfs.dev_id());
+        attr.ino()
+    };
+    let mut inos = ["", "null", "null2", "zero", "foo", "foo/f1"].map(ino);
+    assert_eq!(ino("null"), inos[1]);
+    assert_eq!(inos[0], 1);
+    inos.sort();
+    assert!(inos.windows(2).all(|w| w[0] < w[1]));
+
+    let mut dirents: [VfsDirEntry; 6] = core::array::from_fn(|_| VfsDirEntry::default());
+    assert_eq!(root.read_dir(0, &mut dirents), Ok(6));
+    // `..` of the root directory refers to itself
+    assert_eq!(dirents[1].ino(), ino(""));
+    for ent in dirents.iter().skip(2) {
+        let name = core::str::from_utf8(ent.name_as_bytes()).unwrap();
+        assert_eq!(ent.ino(), ino(name));
+    }
+    assert_ne!(DeviceFileSystem::new().dev_id(), devfs.dev_id());
+}
+
+#[test]
+fn test_device_node() {
+    let devfs = DeviceFileSystem::new();
+    let null: VfsNodeRef = Arc::new(NullDev);
+    devfs.add("null", null.clone());
+
+    let node = devfs.root_dir().lookup("null").unwrap();
+    assert!(!Arc::ptr_eq(&node, &null));
+    assert!(node.as_any().downcast_ref::<NullDev>().is_some());
+    assert!(node.as_any().downcast_ref::<DeviceNode>().is_none());
+    assert!(Arc::ptr_eq(
+        &node,
+        &devfs.root_dir().lookup("/null").unwrap()
+    ));
+}
+
+#[test]
+fn test_rdev() {
+    use axfs_vfs::makedev;
+
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    devfs.add("zero", Arc::new(ZeroDev));
+    devfs.add("urandom", Arc::new(UrandomDev::default()));
+    let root = devfs.root_dir();
+    let rdev = |path| {
+        let attr = root.clone().lookup(path).unwrap().get_attr().unwrap();
+        assert_eq!(attr.file_type(), VfsNodeType::CharDevice);
+        attr.rdev()
+    };
+    assert_eq!(rdev("null"), makedev(1, 3));
+    assert_eq!(rdev("zero"), makedev(1, 5));
+    assert_eq!(rdev("urandom"), makedev(1, 9));
+    assert_eq!(root.get_attr().unwrap().rdev(), 0);
+}
+
+#[test]
+fn test_statfs() {
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    devfs.mkdir("foo").add("zero", Arc::new(ZeroDev));
+
+    let info = devfs.statfs().unwrap();
+    assert_eq!(info.fs_type, 0x01021994);
+    assert_eq!((info.blocks, info.blocks_free, info.files), (0, 0, 4));
+    assert_eq!(info.fsid, devfs.dev_id());
+    assert_eq!(info.name_max, 255);
+}
+
+#[test]
+fn test_ioctl() {
+    const RNDGETENTCNT: u32 = 0x8004_5200;
+    const RNDADDENTROPY: u32 = 0x4008_5203;
+    const TCGETS: u32 = 0x5401;
+
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    devfs.add("urandom", Arc::new(UrandomDev::default()));
+    let root = devfs.root_dir();
+    let null = root.clone().lookup("null").unwrap();
+    let urandom = root.clone().lookup("urandom").unwrap();
+
+    let mut arg = [0; 64];
+    assert_eq!(null.ioctl(TCGETS, &mut arg), Err(VfsError::NotATty));
+    assert_eq!(urandom.ioctl(TCGETS, &mut arg), Err(VfsError::NotATty));
+    assert_eq!(root.ioctl(RNDGETENTCNT, &mut arg), Err(VfsError::NotATty));
+
+    assert_eq!(urandom.ioctl(RNDGETENTCNT, &mut arg), Ok(0));
+    assert_eq!(i32::from_ne_bytes(arg[..4].try_into().unwrap()), 256);
+    assert_eq!(
+        urandom.ioctl(RNDGETENTCNT, &mut arg[..2]),
+        Err(VfsError::InvalidInput)
+    );
+
+    // entropy is mixed into the generator
+    let mut before = [0; 16];
+    let mut after = [0; 16];
+    let reference = UrandomDev::default();
+    reference.read_at(0, &mut before).unwrap();
+    arg[..4].copy_from_slice(&32i32.to_ne_bytes());
+    arg[4..8].copy_from_slice(&4i32.to_ne_bytes());
+    arg[8..12].copy_from_slice(&[1, 2, 3, 4]);
+    assert_eq!(urandom.ioctl(RNDADDENTROPY, &mut arg[..12]), Ok(0));
+    urandom.read_at(0, &mut after).unwrap();
+    assert_ne!(before, after);
+    arg[4..8].copy_from_slice(&8i32.to_ne_bytes());
+    assert_eq!(
+        urandom.ioctl(RNDADDENTROPY, &mut arg[..12]),
+        Err(VfsError::InvalidInput)
+    );
+}
+
+#[test]
+fn test_poll() {
+    use axfs_vfs::{PollEvents, PollSet, VfsNodeAttr, VfsNodePerm};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::task::{Wake, Waker};
+
+    /// A device with a byte queue, that is readable only when not empty.
+    #[derive(Default)]
+    struct QueueDev {
+        data: Mutex<Vec<u8>>,
+        poll_set: PollSet,
+    }
+
+    impl VfsNodeOps for QueueDev {
+        fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+            Ok(VfsNodeAttr::new(
+                VfsNodePerm::default_file(),
+                VfsNodeType::CharDevice,
+                0,
+                0,
+            ))
+        }
+
+        fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+            let mut data = self.data.lock().unwrap();
+            if data.is_empty() {
+                return Err(VfsError::WouldBlock);
+            }
+            let len = buf.len().min(data.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            data.drain(..len);
+            Ok(len)
+        }
+
+        fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+            self.data.lock().unwrap().extend_from_slice(buf);
+            self.poll_set.wake(PollEvents::IN);
+            Ok(buf.len())
+        }
+
+        fn poll(&self, events: PollEvents, waker: Option<&Waker>) -> VfsResult<PollEvents> {
+            let mut ready = PollEvents::OUT;
+            if !self.data.lock().unwrap().is_empty() {
+                ready |= PollEvents::IN;
+            }
+            let ready = ready & events;
+            if let (true, Some(waker)) = (ready.is_empty(), waker) {
+                self.poll_set.register(events, waker);
+            }
+            Ok(ready)
+        }
+
+        axfs_vfs::impl_vfs_non_dir_default! {}
+    }
+
+    #[derive(Default)]
+    struct CountWaker(AtomicUsize);
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    devfs.add("queue", Arc::new(QueueDev::default()));
+    let root = devfs.root_dir();
+    let null = root.clone().lookup("null").unwrap();
+    let queue = root.clone().lookup("queue").unwrap();
+    let count = Arc::new(CountWaker::default());
+    let waker = Waker::from(count.clone());
+    let all = PollEvents::IN | PollEvents::OUT;
+
+    assert_eq!(null.poll(all, Some(&waker)), Ok(all));
+    assert_eq!(queue.poll(all, Some(&waker)), Ok(PollEvents::OUT));
+    assert_eq!(queue.poll(PollEvents::IN, None), Ok(PollEvents::empty()));
+
+    let mut buf = [0; 4];
+    assert_eq!(queue.read_at(0, &mut buf), Err(VfsError::WouldBlock));
+    assert_eq!(
+        queue.poll(PollEvents::IN, Some(&waker)),
+        Ok(PollEvents::empty())
+    );
+    assert_eq!(
+        queue.poll(PollEvents::IN, Some(&waker)),
+        Ok(PollEvents::empty())
+    );
+    assert_eq!(queue.write_at(0, b"hi"), Ok(2));
+    // the same waker is registered only once
+    assert_eq!(count.0.load(Ordering::SeqCst), 1);
+    assert_eq!(queue.poll(PollEvents::IN, Some(&waker)), Ok(PollEvents::IN));
+    assert_eq!(queue.read_at(0, &mut buf), Ok(2));
+    assert_eq!(&buf[..2], b"hi");
+
+    // wakers waiting for other events are kept
+    let poll_set = PollSet::new();
+    poll_set.register(PollEvents::OUT, &waker);
+    assert_eq!(poll_set.wake(PollEvents::IN), 0);
+    assert_eq!(poll_set.wake(PollEvents::HUP), 1);
+    assert!(poll_set.is_empty());
+    assert_eq!(count.0.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_vectored_io() {
+    let devfs = DeviceFileSystem::new();
+    devfs.add("null", Arc::new(NullDev));
+    devfs.add("zero", Arc::new(ZeroDev));
+    devfs.add("urandom", Arc::new(UrandomDev::default()));
+    let root = devfs.root_dir();
+    let null = root.clone().lookup("null").unwrap();
+    let zero = root.clone().lookup("zero").unwrap();
+    let urandom = root.clone().lookup("urandom").unwrap();
+
+    let (mut a, mut b) = ([1; 3], [1; 5]);
+    assert_eq!(null.read_vectored_at(0, &mut [&mut a, &mut b]), Ok(0));
+    assert_eq!(null.write_vectored_at(0, &[&a, &b]), Ok(8));
+    assert_eq!(zero.read_vectored_at(0, &mut [&mut a, &mut b]), Ok(8));
+    assert_eq!((a, b), ([0; 3], [0; 5]));
+    assert_eq!(zero.write_vectored_at(0, &[&a, &b]), Ok(8));
+    assert_eq!(urandom.read_vectored_at(0, &mut [&mut a, &mut b]), Ok(8));
+    assert_ne!(b, [0; 5]);
+    assert_eq!(
+        root.read_vectored_at(0, &mut [&mut a]),
+        Err(VfsError::IsADirectory)
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_async() {
+    use axfs_vfs::{set_executor, to_async_node, to_sync_node, AsyncVfsOps, BlockingNodeAdapter};
+    use axfs_vfs::{PollEvents, PollSet, VfsDirEntry, VfsNodeAttr, VfsNodePerm};
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+
+    /// A pipe-like device with a byte queue, that is readable only when not
+    /// empty, and hangs up when closed. Reading it when empty would block
+    /// even after it is closed.
+    #[derive(Default)]
+    struct PipeDev {
+        data: Mutex<Vec<u8>>,
+        closed: AtomicBool,
+        poll_set: PollSet,
+    }
+
+    impl PipeDev {
+        fn close(&self) {
+            self.closed.store(true, Ordering::SeqCst);
+            self.poll_set.wake(PollEvents::HUP);
+        }
+    }
+
+    impl VfsNodeOps for PipeDev {
+        fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+            Ok(VfsNodeAttr::new(
+                VfsNodePerm::default_file(),
+                VfsNodeType::CharDevice,
+                0,
+                0,
+            ))
+        }
+
+        fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+            let mut data = self.data.lock().unwrap();
+            if data.is_empty() {
+                return Err(VfsError::WouldBlock);
+            }
+            let len = buf.len().min(data.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            data.drain(..len);
+            Ok(len)
+        }
+
+        fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+            self.data.lock().unwrap().extend_from_slice(buf);
+            self.poll_set.wake(PollEvents::IN);
+            Ok(buf.len())
+        }
+
+        fn poll(&self, events: PollEvents, waker: Option<&Waker>) -> VfsResult<PollEvents> {
+            let mut ready = PollEvents::OUT;
+            if !self.data.lock().unwrap().is_empty() {
+                ready |= PollEvents::IN;
+            }
+            let mut ready = ready & events;
+            if self.closed.load(Ordering::SeqCst) {
+                ready |= PollEvents::HUP;
+            }
+            if let (true, Some(waker)) = (ready.is_empty(), waker) {
+                self.poll_set.register(events, waker);
+            }
+            Ok(ready)
+        }
+
+        axfs_vfs::impl_vfs_non_dir_default! {}
+    }
+
+    /// A device that always reports readable, but never has data.
+    struct SpuriousDev;
+
+    impl VfsNodeOps for SpuriousDev {
+        fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
+            Err(VfsError::WouldBlock)
+        }
+
+        fn poll(&self, events: PollEvents, _waker: Option<&Waker>) -> VfsResult<PollEvents> {
+            Ok(events & PollEvents::IN)
+        }
+
+        axfs_vfs::impl_vfs_non_dir_default! {}
+    }
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Runs `future` to completion, parking the thread while it is pending.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    fn run(future: Pin<&mut dyn Future<Output = ()>>) {
+        block_on(future)
+    }
+
+    let devfs = DeviceFileSystem::new();
+    let pipe = Arc::new(PipeDev::default());
+    devfs.add("null", Arc::new(NullDev));
+    devfs.add("pipe", pipe.clone());
+    devfs.mkdir("foo");
+    let root = AsyncVfsOps::root_dir(&devfs);
+
+    let node = block_on(root.clone().lookup("pipe")).unwrap();
+    let attr = block_on(node.get_attr()).unwrap();
+    assert_eq!(attr.file_type(), VfsNodeType::CharDevice);
+    assert_eq!(attr.dev(), AsyncVfsOps::dev_id(&devfs));
+
+    // a read waits until the device becomes readable
+    let writer = {
+        let pipe = pipe.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            pipe.write_at(0, b"hi").unwrap();
+        })
+    };
+    let mut buf = [0; 4];
+    assert_eq!(block_on(node.read_at(0, &mut buf)), Ok(2));
+    assert_eq!(&buf[..2], b"hi");
+    writer.join().unwrap();
+    assert_eq!(block_on(node.write_at(0, b"abc")), Ok(3));
+    assert_eq!(block_on(node.read_at(0, &mut buf)), Ok(3));
+
+    // the blocking adapter only polls once without an executor
+    let blocking = BlockingNodeAdapter::new(node.clone());
+    assert_eq!(blocking.read_at(0, &mut buf), Err(VfsError::WouldBlock));
+    let spurious = BlockingNodeAdapter::new(to_async_node(Arc::new(SpuriousDev)));
+    assert_eq!(spurious.read_at(0, &mut buf), Err(VfsError::WouldBlock));
+    set_executor(run);
+    let writer = {
+        let pipe = pipe.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            pipe.write_at(0, b"hey").unwrap();
+        })
+    };
+    assert_eq!(blocking.read_at(0, &mut buf), Ok(3));
+    writer.join().unwrap();
+
+    // a waiting read fails once the device hangs up
+    let closer = {
+        let pipe = pipe.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            pipe.close();
+        })
+    };
+    assert_eq!(
+        block_on(node.read_at(0, &mut buf)),
+        Err(VfsError::BrokenPipe)
+    );
+    closer.join().unwrap();
+
+    // errors are the same as those of the blocking operations
+    assert_eq!(
+        block_on(node.clone().lookup("f1")).err(),
+        Some(VfsError::NotADirectory)
+    );
+    let foo = block_on(root.clone().lookup("foo")).unwrap();
+    assert_eq!(
+        block_on(foo.read_at(0, &mut buf)),
+        Err(VfsError::IsADirectory)
+    );
+    assert!(foo.parent().is_some());
+    let mut dirents: [VfsDirEntry; 8] = core::array::from_fn(|_| VfsDirEntry::default());
+    assert_eq!(block_on(root.read_dir(0, &mut dirents)), Ok(5));
+    assert_eq!(
+        block_on(root.create("bar", VfsNodeType::File)),
+        Err(VfsError::PermissionDenied)
+    );
+    assert_eq!(
+        block_on(root.rename("null", "foo/null")),
+        Err(VfsError::PermissionDenied)
+    );
+
+    // nodes in the device filesystem are converted without adapters
+    let null = VfsOps::root_dir(&devfs).lookup("null").unwrap();
+    assert!(Arc::ptr_eq(
+        &to_sync_node(to_async_node(null.clone())),
+        &null
+    ));
+}
@@ -1,6 +1,19 @@
-use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use axfs_vfs::{makedev, VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm};
+use axfs_vfs::{VfsNodeType, VfsResult};
 use core::sync::atomic::{AtomicU64, Ordering};
 
+// ioctl commands of the random device, same as Linux.
+const RNDGETENTCNT: u32 = 0x8004_5200;
+const RNDADDTOENTCNT: u32 = 0x4004_5201;
+const RNDADDENTROPY: u32 = 0x4008_5203;
+const RNDZAPENTCNT: u32 = 0x5204;
+const RNDCLEARPOOL: u32 = 0x5206;
+const RNDRESEEDCRNG: u32 = 0x5207;
+
+/// Entropy count in bits reported by `RNDGETENTCNT`. The generator is always
+/// considered fully seeded, as in Linux 5.18 and later.
+const ENTROPY_COUNT: i32 = 256;
+
 /// A urandom device behaves like `/dev/urandom`.
 ///
 /// It produces random bytes when read.
@@ -23,13 +36,23 @@ impl UrandomDev {
 
     /// LCG pseudo-random number generator
     fn next_u64(&self) -> u64 {
-        let new_seed = self
+        let step = |seed: u64| seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        let old_seed = self
             .seed
-            .load(Ordering::SeqCst)
-            .wrapping_mul(6364136223846793005)
-            + 1;
-        self.seed.store(new_seed, Ordering::SeqCst);
-        new_seed
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |seed| Some(step(seed)))
+            .unwrap();
+        step(old_seed)
+    }
+
+    /// Mixes `data` into the seed.
+    fn add_entropy(&self, data: &[u8]) {
+        for chunk in data.chunks(8) {
+            let mut bytes = [0; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            self.seed
+                .fetch_xor(u64::from_ne_bytes(bytes), Ordering::SeqCst);
+            self.next_u64();
+        }
     }
 }
 
@@ -41,12 +64,9 @@ impl Default for UrandomDev {
 
 impl VfsNodeOps for UrandomDev {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new(
-            VfsNodePerm::default_file(),
-            VfsNodeType::CharDevice,
-            0,
-            0,
-        ))
+        let mut attr = VfsNodeAttr::new(VfsNodePerm::default_file(), VfsNodeType::CharDevice, 0, 0);
+        attr.set_rdev(makedev(1, 9));
+        Ok(attr)
     }
 
     fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
@@ -66,9 +86,44 @@ impl VfsNodeOps for UrandomDev {
         Ok(buf.len())
     }
 
+    fn read_vectored_at(&self, offset: u64, bufs: &mut [&mut [u8]]) -> VfsResult<usize> {
+        let mut total = 0;
+        for buf in bufs.iter_mut() {
+            total += self.read_at(offset, buf)?;
+        }
+        Ok(total)
+    }
+
+    fn write_vectored_at(&self, _offset: u64, bufs: &[&[u8]]) -> VfsResult<usize> {
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+    }
+
     fn truncate(&self, _size: u64) -> VfsResult {
         Ok(())
     }
 
+    fn ioctl(&self, cmd: u32, arg: &mut [u8]) -> VfsResult<usize> {
+        match cmd {
+            RNDGETENTCNT => {
+                let count = arg.get_mut(..4).ok_or(VfsError::InvalidInput)?;
+                count.copy_from_slice(&ENTROPY_COUNT.to_ne_bytes());
+            }
+            RNDADDTOENTCNT => {
+                arg.get(..4).ok_or(VfsError::InvalidInput)?;
+            }
+            RNDADDENTROPY => {
+                // struct rand_pool_info { int entropy_count; int buf_size; __u32 buf[]; }
+                let header = arg.get(..8).ok_or(VfsError::InvalidInput)?;
+                let buf_size = i32::from_ne_bytes(header[4..8].try_into().unwrap());
+                let buf_size = usize::try_from(buf_size).map_err(|_| VfsError::InvalidInput)?;
+                let data = arg.get(8..8 + buf_size).ok_or(VfsError::InvalidInput)?;
+                self.add_entropy(data);
+            }
+            RNDZAPENTCNT | RNDCLEARPOOL | RNDRESEEDCRNG => {}
+            _ => return Err(VfsError::NotATty),
+        }
+        Ok(0)
+    }
+
     axfs_vfs::impl_vfs_non_dir_default! {}
 }
@@ -1,4 +1,4 @@
-use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use axfs_vfs::{makedev, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
 
 /// A zero device behaves like `/dev/zero`.
 ///
@@ -7,12 +7,9 @@ pub struct ZeroDev;
 
 impl VfsNodeOps for ZeroDev {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new(
-            VfsNodePerm::default_file(),
-            VfsNodeType::CharDevice,
-            0,
-            0,
-        ))
+        let mut attr = VfsNodeAttr::new(VfsNodePerm::default_file(), VfsNodeType::CharDevice, 0, 0);
+        attr.set_rdev(makedev(1, 5));
+        Ok(attr)
     }
 
     fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
@@ -24,6 +21,15 @@ impl VfsNodeOps for ZeroDev {
         Ok(buf.len())
     }
 
+    fn read_vectored_at(&self, _offset: u64, bufs: &mut [&mut [u8]]) -> VfsResult<usize> {
+        bufs.iter_mut().for_each(|buf| buf.fill(0));
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+    }
+
+    fn write_vectored_at(&self, _offset: u64, bufs: &[&[u8]]) -> VfsResult<usize> {
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+    }
+
     fn truncate(&self, _size: u64) -> VfsResult {
         Ok(())
     }
@@ -0,0 +1,256 @@
+//! Mount table and cross-mount path resolution used by
+//! [ArceOS](https://github.com/arceos-org/arceos).
+//!
+//! A [`MountTable`] owns a root filesystem and any number of filesystems
+//! mounted at directories of it (or of other mounted filesystems). Absolute
+//! paths are resolved component by component, switching to the root of a
+//! mounted filesystem when a mount point is reached, and back to the parent
+//! of the mount point on `..`.
+//!
+//! The implementation is based on [`axfs_vfs`].
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+#[cfg(test)]
+mod tests;
+
+use alloc::collections::BTreeMap;
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+use core::cell::RefCell;
+
+use axfs_vfs::{FileSystemInfo, PathResolver};
+use axfs_vfs::{VfsError, VfsMountFlags, VfsNodeRef, VfsOps, VfsResult};
+use spin::RwLock;
+
+/// A filesystem mounted in a [`MountTable`].
+#[derive(Clone)]
+pub struct MountInfo {
+    /// The canonical absolute path of the mount point.
+    pub path: String,
+    /// The mounted filesystem.
+    pub fs: Arc<dyn VfsOps>,
+    /// The flags the filesystem is mounted with.
+    pub flags: VfsMountFlags,
+}
+
+/// A filesystem mounted in a [`MountTable`], its mount point and mount flags.
+struct Mount {
+    fs: Arc<dyn VfsOps>,
+    mount_point: VfsNodeRef,
+    flags: VfsMountFlags,
+}
+
+/// A table of mounted filesystems.
+pub struct MountTable {
+    root_fs: Arc<dyn VfsOps>,
+    root_flags: VfsMountFlags,
+    mounts: RwLock<BTreeMap<String, Mount>>,
+}
+
+impl MountTable {
+    /// Create a new mount table with `root_fs` mounted at `/`.
+    pub fn new(root_fs: Arc<dyn VfsOps>) -> Self {
+        Self {
+            root_fs,
+            root_flags: VfsMountFlags::empty(),
+            mounts: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Sets the flags the root filesystem is mounted with.
+    pub fn with_root_flags(mut self, flags: VfsMountFlags) -> Self {
+        self.root_flags = flags;
+        self
+    }
+
+    /// Returns the filesystem mounted at `/`.
+    pub fn root_fs(&self) -> &Arc<dyn VfsOps> {
+        &self.root_fs
+    }
+
+    /// Mounts `fs` at the directory `path`.
+    ///
+    /// The mount point must be an existing directory, and no other filesystem
+    /// can be mounted at the same path. Symbolic links in `path` are resolved
+    /// before mounting.
+    pub fn mount(&self, path: &str, fs: Arc<dyn VfsOps>) -> VfsResult {
+        self.mount_with_flags(path, fs, VfsMountFlags::empty())
+    }
+
+    /// Mounts `fs` at the directory `path` with the mount `flags`, which are
+    /// reported by [`statfs()`](Self::statfs).
+    ///
+    /// The flags are only recorded, enforcing them is left to the caller.
+    pub fn mount_with_flags(
+        &self,
+        path: &str,
+        fs: Arc<dyn VfsOps>,
+        flags: VfsMountFlags,
+    ) -> VfsResult {
+        let (mount_point, path) = self.resolve(path, true)?;
+        if path == "/" {
+            return Err(VfsError::ResourceBusy);
+        }
+        if !mount_point.get_attr()?.is_dir() {
+            return Err(VfsError::NotADirectory);
+        }
+
+        let mut mounts = self.mounts.write();
+        if mounts.contains_key(&path) {
+            return Err(VfsError::ResourceBusy);
+        }
+        fs.mount(&path, mount_point.clone())?;
+        log::debug!("mount filesystem at {path}");
+        mounts.insert(
+            path,
+            Mount {
+                fs,
+                mount_point,
+                flags,
+            },
+        );
+        Ok(())
+    }
+
+    /// Unmounts the filesystem mounted at `path`.
+    ///
+    /// Fails with [`VfsError::ResourceBusy`] if other filesystems are still
+    /// mounted under it.
+    pub fn umount(&self, path: &str) -> VfsResult {
+        let (_, path) = self.resolve(path, true)?;
+        let mut mounts = self.mounts.write();
+        let mount = mounts.get(&path).ok_or(VfsError::InvalidInput)?;
+        let prefix = path.clone() + "/";
+        if mounts.keys().any(|p| p.starts_with(&prefix)) {
+            return Err(VfsError::ResourceBusy);
+        }
+        mount.fs.umount()?;
+        log::debug!("umount filesystem at {path}");
+        mounts.remove(&path);
+        Ok(())
+    }
+
+    /// Returns all mounted filesystems, not including the root filesystem,
+    /// sorted by the path of mount points.
+    pub fn mounts(&self) -> Vec<MountInfo> {
+        self.mounts
+            .read()
+            .iter()
+            .map(|(path, mount)| MountInfo {
+                path: path.clone(),
+                fs: mount.fs.clone(),
+                flags: mount.flags,
+            })
+            .collect()
+    }
+
+    /// Get the attributes of the filesystem containing the node at the given
+    /// absolute `path`, including the flags it is mounted with.
+    pub fn statfs(&self, path: &str) -> VfsResult<FileSystemInfo> {
+        let (_, path) = self.resolve(path, true)?;
+        let mounts = self.mounts.read();
+        // the innermost mount point that is an ancestor of the node
+        let mount = mounts
+            .iter()
+            .rev()
+            .find(|(mount_path, _)| {
+                path.strip_prefix(mount_path.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .map(|(_, mount)| mount);
+        let (fs, flags) = match mount {
+            Some(mount) => (&mount.fs, mount.flags),
+            None => (&self.root_fs, self.root_flags),
+        };
+        let mut info = fs.statfs()?;
+        info.flags |= flags;
+        Ok(info)
+    }
+
+    /// Lookup the node with the given absolute `path`, following symbolic
+    /// links.
+    pub fn lookup(&self, path: &str) -> VfsResult<VfsNodeRef> {
+        self.resolve(path, true).map(|(node, _)| node)
+    }
+
+    /// Lookup the node with the given absolute `path`, without following the
+    /// symbolic link at the last component.
+    pub fn lookup_nofollow(&self, path: &str) -> VfsResult<VfsNodeRef> {
+        self.resolve(path, false).map(|(node, _)| node)
+    }
+
+    /// Lookup the parent directory of the given absolute `path`.
+    ///
+    /// Return the parent directory and the last component of the path, which
+    /// can be used to create or remove the node.
+    pub fn lookup_parent<'a>(&self, path: &'a str) -> VfsResult<(VfsNodeRef, &'a str)> {
+        let trimmed = path.trim_end_matches('/');
+        let (parent, name) = trimmed.rsplit_once('/').ok_or(VfsError::InvalidInput)?;
+        if matches!(name, "" | "." | "..") {
+            return Err(VfsError::InvalidInput);
+        }
+        let parent = self.lookup(if parent.is_empty() { "/" } else { parent })?;
+        if !parent.get_attr()?.is_dir() {
+            return Err(VfsError::NotADirectory);
+        }
+        Ok((parent, name))
+    }
+
+    /// Resolves `path` to the node and its physical path, which contains no
+    /// `.`, `..` or symbolic links.
+    ///
+    /// The walk is done by [`PathResolver`], switching to the root of a
+    /// mounted filesystem when a mount point is looked up, and back to the
+    /// parent of the mount point on `..` of a mounted root.
+    fn resolve(&self, path: &str, follow: bool) -> VfsResult<(VfsNodeRef, String)> {
+        if !path.starts_with('/') {
+            return Err(VfsError::InvalidInput);
+        }
+        let mounts = self.mounts.read();
+        let root = self.root_fs.root_dir();
+
+        // nodes walked through and their physical paths, every node reached by
+        // the resolver is either looked up or an ancestor of one
+        let walked = RefCell::new(vec![(root.clone(), String::new())]);
+        let lookup = |dir: &VfsNodeRef, name: &str| {
+            if name == ".." {
+                let mount = mounts
+                    .values()
+                    .find(|mount| Arc::ptr_eq(&mount.fs.root_dir(), dir));
+                let dir = mount.map_or(dir, |mount| &mount.mount_point);
+                return Ok(dir.parent().unwrap_or_else(|| dir.clone()));
+            }
+            let mut path = path_of(&walked.borrow(), dir)?;
+            path.push('/');
+            path.push_str(name);
+            let node = dir.clone().lookup_nofollow(name)?;
+            let node = match mounts.get(&path) {
+                Some(mount) => mount.fs.root_dir(),
+                None => node,
+            };
+            walked.borrow_mut().push((node.clone(), path));
+            Ok(node)
+        };
+        let node = PathResolver::new()
+            .with_root(root.clone())
+            .with_lookup(&lookup)
+            .resolve(&root, path, follow)?;
+
+        let mut path = path_of(&walked.borrow(), &node)?;
+        if path.is_empty() {
+            path.push('/');
+        }
+        Ok((node, path))
+    }
+}
+
+fn path_of(walked: &[(VfsNodeRef, String)], node: &VfsNodeRef) -> VfsResult<String> {
+    walked
+        .iter()
+        .rev()
+        .find(|(walked, _)| Arc::ptr_eq(walked, node))
+        .map(|(_, path)| path.clone())
+        .ok_or(VfsError::NotFound)
+}
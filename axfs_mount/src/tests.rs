@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use axfs_devfs::{DeviceFileSystem, NullDev};
+use axfs_ramfs::RamFileSystem;
+use axfs_vfs::{VfsError, VfsMountFlags, VfsNodeType, VfsOps};
+
+use crate::*;
+
+fn new_table() -> (MountTable, Arc<DeviceFileSystem>, Arc<RamFileSystem>) {
+    // /
+    // ├── dev (devfs)
+    // │   └── null
+    // ├── tmp (ramfs)
+    // │   ├── up -> ../dev
+    // │   └── sub
+    // ├── stdin -> /dev/null
+    // └── lnk -> tmp
+
+    let rootfs = Arc::new(RamFileSystem::new());
+    let root = rootfs.root_dir();
+    root.create("dev", VfsNodeType::Dir).unwrap();
+    root.create("tmp", VfsNodeType::Dir).unwrap();
+    root.symlink("stdin", "/dev/null").unwrap();
+    root.symlink("lnk", "tmp").unwrap();
+
+    let devfs = Arc::new(DeviceFileSystem::new());
+    devfs.add("null", Arc::new(NullDev));
+    let tmpfs = Arc::new(RamFileSystem::new());
+    tmpfs.root_dir().symlink("up", "../dev").unwrap();
+    tmpfs.root_dir().create("sub", VfsNodeType::Dir).unwrap();
+
+    let table = MountTable::new(rootfs);
+    table.mount("/dev", devfs.clone()).unwrap();
+    table.mount("/lnk/", tmpfs.clone()).unwrap();
+    (table, devfs, tmpfs)
+}
+
+#[test]
+fn test_mount_lookup() {
+    let (table, devfs, tmpfs) = new_table();
+    let root = table.root_fs().root_dir();
+    let null = devfs.root_dir().lookup("null").unwrap();
+
+    assert!(Arc::ptr_eq(&table.lookup("/").unwrap(), &root));
+    assert!(Arc::ptr_eq(
+        &table.lookup("/dev").unwrap(),
+        &devfs.root_dir()
+    ));
+    assert!(Arc::ptr_eq(&table.lookup("/dev/null").unwrap(), &null));
+    assert!(Arc::ptr_eq(
+        &table.lookup("/tmp/").unwrap(),
+        &tmpfs.root_dir()
+    ));
+    assert!(Arc::ptr_eq(&table.lookup("/dev/..").unwrap(), &root));
+    assert!(Arc::ptr_eq(&table.lookup("/dev/../..").unwrap(), &root));
+    assert!(Arc::ptr_eq(
+        &table.lookup("/tmp/sub/../../dev/./null").unwrap(),
+        &null
+    ));
+
+    // symbolic links across mount boundaries
+    assert!(Arc::ptr_eq(&table.lookup("/stdin").unwrap(), &null));
+    assert!(Arc::ptr_eq(&table.lookup("/lnk/up/null").unwrap(), &null));
+    assert!(Arc::ptr_eq(
+        &table.lookup("/tmp/up").unwrap(),
+        &devfs.root_dir()
+    ));
+    assert!(Arc::ptr_eq(&table.lookup("/lnk/..").unwrap(), &root));
+    assert!(table
+        .lookup_nofollow("/stdin")
+        .unwrap()
+        .get_attr()
+        .unwrap()
+        .is_symlink());
+    assert_eq!(table.lookup("/stdin/").err(), Some(VfsError::NotADirectory));
+    assert_eq!(
+        table.lookup("/stdin/..").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        table.lookup("/dev/null/.").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(table.lookup("/dev/zero").err(), Some(VfsError::NotFound));
+    assert_eq!(table.lookup("dev").err(), Some(VfsError::InvalidInput));
+
+    let (parent, name) = table.lookup_parent("/lnk/sub/f1").unwrap();
+    assert_eq!(name, "f1");
+    parent.create(name, VfsNodeType::File).unwrap();
+    assert!(tmpfs.root_dir().lookup("sub/f1").is_ok());
+    assert_eq!(
+        table.lookup_parent("/stdin/f1").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(table.lookup_parent("/").err(), Some(VfsError::InvalidInput));
+}
+
+#[test]
+fn test_mount_umount() {
+    let (table, _devfs, _tmpfs) = new_table();
+    let paths: Vec<_> = table.mounts().into_iter().map(|m| m.path).collect();
+    assert_eq!(paths, ["/dev", "/tmp"]);
+
+    let nested = Arc::new(RamFileSystem::new());
+    assert_eq!(
+        table.mount("/tmp", nested.clone()).err(),
+        Some(VfsError::ResourceBusy)
+    );
+    assert_eq!(
+        table.mount("/", nested.clone()).err(),
+        Some(VfsError::ResourceBusy)
+    );
+    assert_eq!(
+        table.mount("/stdin", nested.clone()).err(),
+        Some(VfsError::NotADirectory)
+    );
+    table.mount("/tmp/sub", nested.clone()).unwrap();
+    nested.root_dir().create("f2", VfsNodeType::File).unwrap();
+    assert!(table.lookup("/lnk/sub/f2").is_ok());
+    assert!(Arc::ptr_eq(
+        &table.lookup("/tmp/sub/..").unwrap(),
+        &table.lookup("/tmp").unwrap()
+    ));
+
+    assert_eq!(table.umount("/tmp").err(), Some(VfsError::ResourceBusy));
+    assert_eq!(table.umount("/tmp/sub/.."), Err(VfsError::ResourceBusy));
+    table.umount("/lnk/sub").unwrap();
+    assert_eq!(table.lookup("/tmp/sub/f2").err(), Some(VfsError::NotFound));
+    table.umount("/tmp").unwrap();
+    assert_eq!(table.lookup("/tmp/sub").err(), Some(VfsError::NotFound));
+    assert_eq!(table.umount("/tmp").err(), Some(VfsError::InvalidInput));
+    assert_eq!(table.mounts().len(), 1);
+}
+
+#[test]
+fn test_mount_remount() {
+    let (table, _devfs, tmpfs) = new_table();
+    let root = table.root_fs().root_dir();
+    root.create("a", VfsNodeType::Dir).unwrap();
+    root.create("a/b", VfsNodeType::Dir).unwrap();
+    let a = root.clone().lookup("a").unwrap();
+
+    // `..` of a mounted root goes to the parent of its current mount point
+    table.umount("/tmp").unwrap();
+    table.mount("/a/b", tmpfs.clone()).unwrap();
+    assert!(Arc::ptr_eq(&table.lookup("/a/b/..").unwrap(), &a));
+    assert!(Arc::ptr_eq(&table.lookup("/a/b/sub/../..").unwrap(), &a));
+    // so are relative symbolic links in it
+    assert_eq!(table.lookup("/a/b/up").err(), Some(VfsError::NotFound));
+    let paths: Vec<_> = table.mounts().into_iter().map(|m| m.path).collect();
+    assert_eq!(paths, ["/a/b", "/dev"]);
+}
+
+#[test]
+fn test_mount_flags() {
+    let (table, devfs, tmpfs) = new_table();
+    let table = table.with_root_flags(VfsMountFlags::NOATIME);
+    let nested = Arc::new(RamFileSystem::new());
+    let flags = VfsMountFlags::RDONLY | VfsMountFlags::NOEXEC;
+    table
+        .mount_with_flags("/tmp/sub", nested.clone(), flags)
+        .unwrap();
+
+    let info = table.statfs("/").unwrap();
+    assert_eq!(info.fsid, table.root_fs().dev_id());
+    assert_eq!(info.flags, VfsMountFlags::NOATIME);
+    let info = table.statfs("/stdin").unwrap();
+    assert_eq!(info.fsid, devfs.dev_id());
+    assert_eq!(info.flags, VfsMountFlags::empty());
+    assert_eq!(table.statfs("/lnk").unwrap().fsid, tmpfs.dev_id());
+    let info = table.statfs("/tmp/sub/.").unwrap();
+    assert_eq!(info.fsid, nested.dev_id());
+    assert_eq!(info.flags, flags);
+    assert_eq!(table.mounts()[2].flags, flags);
+    assert_eq!(table.statfs("/tmp/f1").err(), Some(VfsError::NotFound));
+}
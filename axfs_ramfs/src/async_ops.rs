@@ -0,0 +1,45 @@
+//! Native async operations of the RAM filesystem.
+//!
+//! File contents are kept in memory, so no operation ever waits, and the
+//! futures complete on the first poll.
+
+use alloc::boxed::Box;
+use axfs_vfs::{to_sync_node, AsyncVfsNodeOps, AsyncVfsNodeRef, AsyncVfsOps, FileSystemInfo};
+use axfs_vfs::{VfsFuture, VfsOps};
+
+use crate::{DirNode, FileNode, RamFileSystem, SpecialNode, SymlinkNode};
+
+impl AsyncVfsOps for RamFileSystem {
+    fn mount<'a>(&'a self, path: &'a str, mount_point: AsyncVfsNodeRef) -> VfsFuture<'a> {
+        let mount_point = to_sync_node(mount_point);
+        Box::pin(async move { VfsOps::mount(self, path, mount_point) })
+    }
+
+    fn statfs(&self) -> VfsFuture<'_, FileSystemInfo> {
+        Box::pin(async move { VfsOps::statfs(self) })
+    }
+
+    fn root_dir(&self) -> AsyncVfsNodeRef {
+        self.root_dir_node()
+    }
+
+    fn dev_id(&self) -> u64 {
+        VfsOps::dev_id(self)
+    }
+}
+
+impl AsyncVfsNodeOps for FileNode {
+    axfs_vfs::impl_vfs_async_from_sync! {}
+}
+
+impl AsyncVfsNodeOps for DirNode {
+    axfs_vfs::impl_vfs_async_from_sync! {}
+}
+
+impl AsyncVfsNodeOps for SymlinkNode {
+    axfs_vfs::impl_vfs_async_from_sync! {}
+}
+
+impl AsyncVfsNodeOps for SpecialNode {
+    axfs_vfs::impl_vfs_async_from_sync! {}
+}
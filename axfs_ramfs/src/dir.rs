@@ -1,12 +1,86 @@
 use alloc::collections::BTreeMap;
 use alloc::sync::{Arc, Weak};
 use alloc::{string::String, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
 
-use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
-use axfs_vfs::{VfsError, VfsResult};
+use axfs_vfs::{LockManager, RenameFlags, VfsError, VfsResult, XattrFlags};
+use axfs_vfs::{VfsAttrMask, VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodePerm};
+use axfs_vfs::{VfsDirOps, VfsNodeRef, VfsNodeType, NAME_MAX};
 use spin::RwLock;
 
 use crate::file::FileNode;
+use crate::meta::{FsContext, NodeMeta};
+use crate::special::SpecialNode;
+use crate::symlink::SymlinkNode;
+
+/// The `read_dir` cookie of the first entry after `.` and `..`.
+const FIRST_COOKIE: u64 = 2;
+
+/// Entries of a directory.
+///
+/// Each entry is assigned an increasing sequence number when inserted, which
+/// is used as its `read_dir` cookie. A listing resumed from a cookie scans the
+/// entries in the order of sequence numbers, so it is not affected by entries
+/// inserted or removed in between.
+struct Children {
+    next_seq: u64,
+    by_name: BTreeMap<String, (u64, VfsNodeRef)>,
+    by_seq: BTreeMap<u64, String>,
+}
+
+impl Children {
+    const fn new() -> Self {
+        Self {
+            next_seq: FIRST_COOKIE,
+            by_name: BTreeMap::new(),
+            by_seq: BTreeMap::new(),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&VfsNodeRef> {
+        self.by_name.get(name).map(|(_, node)| node)
+    }
+
+    fn contains_key(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &String> {
+        self.by_name.keys()
+    }
+
+    fn values(&self) -> impl Iterator<Item = &VfsNodeRef> {
+        self.by_name.values().map(|(_, node)| node)
+    }
+
+    /// Inserts an entry, and returns the node it replaces, if any.
+    fn insert(&mut self, name: String, node: VfsNodeRef) -> Option<VfsNodeRef> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.by_seq.insert(seq, name.clone());
+        let (old_seq, old) = self.by_name.insert(name, (seq, node))?;
+        self.by_seq.remove(&old_seq);
+        Some(old)
+    }
+
+    fn remove(&mut self, name: &str) -> Option<VfsNodeRef> {
+        let (seq, node) = self.by_name.remove(name)?;
+        self.by_seq.remove(&seq);
+        Some(node)
+    }
+
+    /// Returns the entries from the cookie `start`, with the cookie of each
+    /// entry.
+    fn range(&self, start: u64) -> impl Iterator<Item = (u64, &str, &VfsNodeRef)> {
+        self.by_seq
+            .range(start..)
+            .map(|(&seq, name)| (seq, name.as_str(), &self.by_name[name].1))
+    }
+}
 
 /// The directory node in the RAM filesystem.
 ///
@@ -14,15 +88,21 @@ use crate::file::FileNode;
 pub struct DirNode {
     this: Weak<DirNode>,
     parent: RwLock<Weak<dyn VfsNodeOps>>,
-    children: RwLock<BTreeMap<String, VfsNodeRef>>,
+    children: RwLock<Children>,
+    pub(super) meta: NodeMeta,
 }
 
 impl DirNode {
-    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>) -> Arc<Self> {
+    pub(super) fn new(
+        parent: Option<Weak<dyn VfsNodeOps>>,
+        fs: &Arc<FsContext>,
+        perm: VfsNodePerm,
+    ) -> Arc<Self> {
         Arc::new_cyclic(|this| Self {
             this: this.clone(),
             parent: RwLock::new(parent.unwrap_or_else(|| Weak::<Self>::new())),
-            children: RwLock::new(BTreeMap::new()),
+            children: RwLock::new(Children::new()),
+            meta: NodeMeta::new(fs, perm),
         })
     }
 
@@ -41,17 +121,93 @@ impl DirNode {
     }
 
     /// Creates a new node with the given name and type in this directory.
+    ///
+    /// The node has the default permission of its type, and character and
+    /// block devices are created with device ID 0.
     pub fn create_node(&self, name: &str, ty: VfsNodeType) -> VfsResult {
-        if self.exist(name) {
+        let perm = if ty.is_dir() {
+            VfsNodePerm::default_dir()
+        } else {
+            VfsNodePerm::default_file()
+        };
+        self.mknod_node(name, ty, perm, 0)
+    }
+
+    /// Creates a new node with the given name, type, permission and device
+    /// ID in this directory.
+    ///
+    /// The device ID is only used for character and block devices. Symbolic
+    /// links should be created with [`create_symlink()`](Self::create_symlink).
+    pub fn mknod_node(
+        &self,
+        name: &str,
+        ty: VfsNodeType,
+        perm: VfsNodePerm,
+        rdev: u64,
+    ) -> VfsResult {
+        check_name(name)?;
+        let mut children = self.children.write();
+        if children.contains_key(name) {
             log::error!("AlreadyExists {name}");
             return Err(VfsError::AlreadyExists);
         }
+        let fs = self.meta.fs();
         let node: VfsNodeRef = match ty {
-            VfsNodeType::File => Arc::new(FileNode::new()),
-            VfsNodeType::Dir => Self::new(Some(self.this.clone())),
-            _ => return Err(VfsError::Unsupported),
+            VfsNodeType::File => Arc::new(FileNode::new(fs, perm)),
+            VfsNodeType::Dir => Self::new(Some(self.this.clone()), fs, perm),
+            VfsNodeType::CharDevice | VfsNodeType::BlockDevice => {
+                Arc::new(SpecialNode::new(fs, ty, perm, rdev))
+            }
+            VfsNodeType::Fifo | VfsNodeType::Socket => Arc::new(SpecialNode::new(fs, ty, perm, 0)),
+            VfsNodeType::SymLink => return Err(VfsError::Unsupported),
         };
-        self.children.write().insert(name.into(), node);
+        children.insert(name.into(), node);
+        self.meta.touch_mtime();
+        Ok(())
+    }
+
+    /// Creates a new symbolic link with the given name in this directory,
+    /// which points to `target`.
+    pub fn create_symlink(&self, name: &str, target: &str) -> VfsResult {
+        if target.is_empty() {
+            return Err(VfsError::NotFound);
+        }
+        check_name(name)?;
+        let mut children = self.children.write();
+        if children.contains_key(name) {
+            log::error!("AlreadyExists {name}");
+            return Err(VfsError::AlreadyExists);
+        }
+        let node = Arc::new(SymlinkNode::new(self.meta.fs(), target));
+        children.insert(name.into(), node);
+        self.meta.touch_mtime();
+        Ok(())
+    }
+
+    /// Creates a new hard link with the given name in this directory, which
+    /// refers to the existing `node`.
+    ///
+    /// Only files, special files and symbolic links in the same RAM filesystem
+    /// can be linked.
+    pub fn link_node(&self, name: &str, node: &VfsNodeRef) -> VfsResult {
+        check_name(name)?;
+        let nlink = match nlink_of(node) {
+            Some(nlink) => nlink,
+            None if node.as_any().is::<DirNode>() => return Err(VfsError::OperationNotPermitted),
+            None => return Err(VfsError::CrossesDevices),
+        };
+        if !Arc::ptr_eq(meta_of(node).unwrap().fs(), self.meta.fs()) {
+            return Err(VfsError::CrossesDevices);
+        }
+        let mut children = self.children.write();
+        if children.contains_key(name) {
+            log::error!("AlreadyExists {name}");
+            return Err(VfsError::AlreadyExists);
+        }
+        nlink.fetch_add(1, Ordering::AcqRel);
+        children.insert(name.into(), node.clone());
+        meta_of(node).unwrap().touch_ctime();
+        self.meta.touch_mtime();
         Ok(())
     }
 
@@ -64,113 +220,323 @@ impl DirNode {
                 return Err(VfsError::DirectoryNotEmpty);
             }
         }
-        children.remove(name);
+        if let Some(node) = children.remove(name) {
+            if let Some(nlink) = nlink_of(&node) {
+                nlink.fetch_sub(1, Ordering::AcqRel);
+                meta_of(&node).unwrap().touch_ctime();
+            }
+        }
+        self.meta.touch_mtime();
         Ok(())
     }
+
+    /// Renames the node `src_name` in this directory to `dst_name` in
+    /// `dst_dir`, which must be in the same RAM filesystem.
+    pub fn rename_node(
+        &self,
+        src_name: &str,
+        dst_dir: &DirNode,
+        dst_name: &str,
+        flags: RenameFlags,
+    ) -> VfsResult {
+        if flags.contains(RenameFlags::NOREPLACE | RenameFlags::EXCHANGE)
+            || !RenameFlags::all().contains(flags)
+        {
+            return Err(VfsError::InvalidInput);
+        }
+        check_name(src_name)?;
+        check_name(dst_name)?;
+        let _rename = self.meta.fs().lock_rename();
+        let same_dir = core::ptr::eq(self, dst_dir);
+        // other operations lock a parent before its children, so lock the
+        // ancestor first, or in address order if neither is an ancestor
+        let src_first = self.is_ancestor_of(dst_dir)
+            || (!dst_dir.is_ancestor_of(self) && (self as *const Self) < (dst_dir as *const Self));
+        let (mut src_children, mut dst_children) = if same_dir {
+            (self.children.write(), None)
+        } else if src_first {
+            let src_children = self.children.write();
+            (src_children, Some(dst_dir.children.write()))
+        } else {
+            let dst_children = dst_dir.children.write();
+            (self.children.write(), Some(dst_children))
+        };
+
+        let src = src_children
+            .get(src_name)
+            .cloned()
+            .ok_or(VfsError::NotFound)?;
+        let dst = dst_children
+            .as_deref()
+            .unwrap_or(&src_children)
+            .get(dst_name)
+            .cloned();
+        match &dst {
+            // both names refer to the same node
+            Some(dst) if Arc::ptr_eq(&src, dst) => return Ok(()),
+            Some(_) if flags.contains(RenameFlags::NOREPLACE) => {
+                return Err(VfsError::AlreadyExists)
+            }
+            None if flags.contains(RenameFlags::EXCHANGE) => return Err(VfsError::NotFound),
+            _ => {}
+        }
+        let src_subdir = src.as_any().downcast_ref::<DirNode>();
+        let dst_subdir = dst
+            .as_ref()
+            .and_then(|dst| dst.as_any().downcast_ref::<DirNode>());
+        // a directory cannot be moved into itself or its subdirectories
+        if src_subdir.is_some_and(|dir| dir.is_ancestor_of(dst_dir)) {
+            return Err(VfsError::InvalidInput);
+        }
+        if flags.contains(RenameFlags::EXCHANGE) {
+            if dst_subdir.is_some_and(|dir| dir.is_ancestor_of(self)) {
+                return Err(VfsError::InvalidInput);
+            }
+        } else if dst.is_some() {
+            match (src_subdir, dst_subdir) {
+                (Some(_), None) => return Err(VfsError::NotADirectory),
+                (None, Some(_)) => return Err(VfsError::IsADirectory),
+                // the target contains the source if it is one of its ancestors
+                (Some(_), Some(dir))
+                    if dir.is_ancestor_of(self) || !dir.children.read().is_empty() =>
+                {
+                    return Err(VfsError::DirectoryNotEmpty)
+                }
+                _ => {}
+            }
+        }
+
+        let this: VfsNodeRef = self.this.upgrade().unwrap();
+        let dst_this: VfsNodeRef = dst_dir.this.upgrade().unwrap();
+        if flags.contains(RenameFlags::EXCHANGE) {
+            let dst = dst.as_ref().unwrap();
+            src_children.insert(src_name.into(), dst.clone());
+            if let Some(dir) = dst_subdir {
+                dir.set_parent(Some(&this));
+            }
+            meta_of(dst).unwrap().touch_ctime();
+        } else {
+            src_children.remove(src_name);
+        }
+        let replaced = dst_children
+            .as_deref_mut()
+            .unwrap_or(&mut src_children)
+            .insert(dst_name.into(), src.clone());
+        if let Some(old) = replaced.filter(|_| !flags.contains(RenameFlags::EXCHANGE)) {
+            if let Some(nlink) = nlink_of(&old) {
+                nlink.fetch_sub(1, Ordering::AcqRel);
+            }
+            meta_of(&old).unwrap().touch_ctime();
+        }
+        if let Some(dir) = src_subdir {
+            dir.set_parent(Some(&dst_this));
+        }
+        meta_of(&src).unwrap().touch_ctime();
+        self.meta.touch_mtime();
+        dst_dir.meta.touch_mtime();
+        Ok(())
+    }
+
+    /// Whether this directory is `dir` or one of its ancestors.
+    fn is_ancestor_of(&self, dir: &DirNode) -> bool {
+        if core::ptr::eq(self, dir) {
+            return true;
+        }
+        let mut parent = dir.parent();
+        while let Some(node) = parent {
+            // stop at the root of the RAM filesystem
+            let Some(dir) = node.as_any().downcast_ref::<DirNode>() else {
+                return false;
+            };
+            if core::ptr::eq(self, dir) {
+                return true;
+            }
+            parent = dir.parent();
+        }
+        false
+    }
+
+    fn parent_ino(&self) -> u64 {
+        self.parent()
+            .and_then(|parent| parent.get_attr().ok())
+            .map_or(self.meta.ino(), |attr| attr.ino())
+    }
 }
 
 impl VfsNodeOps for DirNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_dir(4096, 0))
+        let mut attr = VfsNodeAttr::new_dir(4096, 0);
+        let subdirs = self
+            .children
+            .read()
+            .values()
+            .filter(|node| node.as_any().is::<DirNode>())
+            .count();
+        attr.set_nlink(2 + subdirs as u64);
+        self.meta.fill_attr(&mut attr);
+        Ok(attr)
     }
 
-    fn parent(&self) -> Option<VfsNodeRef> {
-        self.parent.read().upgrade()
+    fn set_attr(&self, attr: &VfsNodeAttr, mask: VfsAttrMask) -> VfsResult {
+        self.meta.set_attr(attr, mask);
+        Ok(())
     }
 
-    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
-        let (name, rest) = split_path(path);
-        let node = match name {
-            "" | "." => Ok(self.clone() as VfsNodeRef),
-            ".." => self.parent().ok_or(VfsError::NotFound),
-            _ => self
-                .children
-                .read()
-                .get(name)
-                .cloned()
-                .ok_or(VfsError::NotFound),
-        }?;
-
-        if let Some(rest) = rest {
-            node.lookup(rest)
-        } else {
-            Ok(node)
-        }
+    fn get_xattr(&self, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.get_xattr(name, buf)
+    }
+
+    fn set_xattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> VfsResult {
+        self.meta.set_xattr(name, value, flags, true)
+    }
+
+    fn list_xattr(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.list_xattr(buf)
+    }
+
+    fn remove_xattr(&self, name: &str) -> VfsResult {
+        self.meta.remove_xattr(name)
+    }
+
+    fn lock_manager(&self) -> Option<&LockManager> {
+        Some(self.meta.locks())
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.parent.read().upgrade()
     }
 
-    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+    fn read_dir(&self, cookie: u64, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
         let children = self.children.read();
-        let mut children = children.iter().skip(start_idx.max(2) - 2);
+        let mut children = children.range(cookie.max(FIRST_COOKIE));
+        let mut cookie = cookie;
         for (i, ent) in dirents.iter_mut().enumerate() {
-            match i + start_idx {
-                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
-                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+            match cookie {
+                0 => {
+                    *ent = VfsDirEntry::new(".", VfsNodeType::Dir);
+                    ent.set_ino(self.meta.ino());
+                    cookie = 1;
+                }
+                1 => {
+                    *ent = VfsDirEntry::new("..", VfsNodeType::Dir);
+                    ent.set_ino(self.parent_ino());
+                    cookie = FIRST_COOKIE;
+                }
                 _ => {
-                    if let Some((name, node)) = children.next() {
-                        *ent = VfsDirEntry::new(name, node.get_attr().unwrap().file_type());
+                    if let Some((seq, name, node)) = children.next() {
+                        let attr = node.get_attr().unwrap();
+                        *ent = VfsDirEntry::new(name, attr.file_type());
+                        ent.set_ino(attr.ino());
+                        cookie = seq + 1;
                     } else {
+                        self.meta.touch_atime();
                         return Ok(i);
                     }
                 }
             }
+            ent.set_offset(cookie);
         }
+        self.meta.touch_atime();
         Ok(dirents.len())
     }
 
-    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
-        log::debug!("create {ty:?} at ramfs: {path}");
-        let (name, rest) = split_path(path);
-        if let Some(rest) = rest {
-            match name {
-                "" | "." => self.create(rest, ty),
-                ".." => self.parent().ok_or(VfsError::NotFound)?.create(rest, ty),
-                _ => {
-                    let subdir = self
-                        .children
-                        .read()
-                        .get(name)
-                        .ok_or(VfsError::NotFound)?
-                        .clone();
-                    subdir.create(rest, ty)
-                }
-            }
-        } else if name.is_empty() || name == "." || name == ".." {
-            Ok(()) // already exists
-        } else {
-            self.create_node(name, ty)
-        }
+    axfs_vfs::impl_vfs_dir_paths! {}
+    #[cfg(feature = "async")]
+    fn into_async(self: Arc<Self>) -> Option<axfs_vfs::AsyncVfsNodeRef> {
+        Some(self)
     }
 
-    fn remove(&self, path: &str) -> VfsResult {
-        log::debug!("remove at ramfs: {path}");
-        let (name, rest) = split_path(path);
-        if let Some(rest) = rest {
-            match name {
-                "" | "." => self.remove(rest),
-                ".." => self.parent().ok_or(VfsError::NotFound)?.remove(rest),
-                _ => {
-                    let subdir = self
-                        .children
-                        .read()
-                        .get(name)
-                        .ok_or(VfsError::NotFound)?
-                        .clone();
-                    subdir.remove(rest)
-                }
+    axfs_vfs::impl_vfs_dir_default! {}
+}
+
+impl VfsDirOps for DirNode {
+    fn node_ref(&self) -> VfsNodeRef {
+        self.this.upgrade().unwrap()
+    }
+
+    fn lookup_entry(&self, name: &str) -> VfsResult<VfsNodeRef> {
+        self.children
+            .read()
+            .get(name)
+            .cloned()
+            .ok_or(VfsError::NotFound)
+    }
+
+    fn create_entry(&self, name: &str, ty: VfsNodeType) -> VfsResult {
+        log::debug!("create {ty:?} at ramfs: {name}");
+        self.create_node(name, ty)
+    }
+
+    fn mknod_entry(&self, name: &str, ty: VfsNodeType, perm: VfsNodePerm, rdev: u64) -> VfsResult {
+        log::debug!("mknod {ty:?} at ramfs: {name}");
+        self.mknod_node(name, ty, perm, rdev)
+    }
+
+    fn symlink_entry(&self, name: &str, target: &str) -> VfsResult {
+        log::debug!("symlink at ramfs: {name} -> {target}");
+        self.create_symlink(name, target)
+    }
+
+    fn link_entry(&self, name: &str, node: &VfsNodeRef) -> VfsResult {
+        log::debug!("link at ramfs: {name}");
+        self.link_node(name, node)
+    }
+
+    fn remove_entry(&self, name: &str) -> VfsResult {
+        log::debug!("remove at ramfs: {name}");
+        self.remove_node(name)
+    }
+
+    fn rename_entry(
+        &self,
+        src_name: &str,
+        dst_dir: &VfsNodeRef,
+        dst_name: &str,
+        flags: RenameFlags,
+    ) -> VfsResult {
+        log::debug!("rename at ramfs: {src_name} -> {dst_name} ({flags:?})");
+        if dst_dir.get_attr()?.dev() != self.meta.fs().dev() {
+            return Err(VfsError::CrossesDevices);
+        }
+        match dst_dir.as_any().downcast_ref::<DirNode>() {
+            Some(dir) if Arc::ptr_eq(dir.meta.fs(), self.meta.fs()) => {
+                self.rename_node(src_name, dir, dst_name, flags)
             }
-        } else if name.is_empty() || name == "." || name == ".." {
-            Err(VfsError::InvalidInput) // remove '.' or '..
-        } else {
-            self.remove_node(name)
+            _ => Err(VfsError::CrossesDevices),
         }
     }
+}
 
-    axfs_vfs::impl_vfs_dir_default! {}
+/// Returns the hard link counter of `node`, if it can be hard linked.
+fn nlink_of(node: &VfsNodeRef) -> Option<&AtomicU64> {
+    let node = node.as_any();
+    if let Some(file) = node.downcast_ref::<FileNode>() {
+        Some(&file.nlink)
+    } else if let Some(special) = node.downcast_ref::<SpecialNode>() {
+        Some(&special.nlink)
+    } else {
+        node.downcast_ref::<SymlinkNode>().map(|link| &link.nlink)
+    }
 }
 
-fn split_path(path: &str) -> (&str, Option<&str>) {
-    let trimmed_path = path.trim_start_matches('/');
-    trimmed_path.find('/').map_or((trimmed_path, None), |n| {
-        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
-    })
+/// Returns the metadata of `node`, if it is in the RAM filesystem.
+fn meta_of(node: &VfsNodeRef) -> Option<&NodeMeta> {
+    let node = node.as_any();
+    if let Some(dir) = node.downcast_ref::<DirNode>() {
+        Some(&dir.meta)
+    } else if let Some(file) = node.downcast_ref::<FileNode>() {
+        Some(&file.meta)
+    } else if let Some(special) = node.downcast_ref::<SpecialNode>() {
+        Some(&special.meta)
+    } else {
+        node.downcast_ref::<SymlinkNode>().map(|link| &link.meta)
+    }
+}
+
+/// Returns [`VfsError::NameTooLong`] if `name` is longer than [`NAME_MAX`].
+fn check_name(name: &str) -> VfsResult {
+    if name.len() > NAME_MAX {
+        return Err(VfsError::NameTooLong);
+    }
+    Ok(())
 }
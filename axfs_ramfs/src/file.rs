@@ -1,56 +1,588 @@
-use alloc::vec::Vec;
+use alloc::collections::BTreeMap;
+use alloc::{sync::Arc, vec::Vec};
+use axfs_vfs::{copy_buffer, generic_copy_range, FallocateMode, FilePage, PageRef, PAGE_SIZE};
 use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{LockManager, VfsAttrMask, VfsError, VfsNodePerm, VfsNodeRef, XattrFlags};
+use core::ops::Range;
+use core::sync::atomic::{AtomicU64, Ordering};
 use spin::RwLock;
 
+use crate::meta::{FsContext, NodeMeta, BLOCK_SIZE};
+
+const PAGE_SIZE_U64: u64 = PAGE_SIZE as u64;
+
+/// A page of [`Content`], which may be shared by files cloned with
+/// `copy_range` until one of them writes to it.
+///
+/// Pages returned by `get_pages` are never shared between files, otherwise
+/// writes through mappings would show up in all of them.
+type PageSlot = Arc<PageRef>;
+
+/// Returns `true` if the page may be mapped, i.e. it has been returned by
+/// `get_pages` and is still referenced.
+fn is_mapped(slot: &PageSlot) -> bool {
+    Arc::strong_count(slot.as_ref()) > 1
+}
+
+/// Content of a file, stored in pages that can be shared with mappings.
+///
+/// Pages that have never been written are not allocated, and read as zeros.
+/// Pages can also be allocated beyond the end of file by `fallocate`.
+///
+/// The content is behind the lock of its file, and pages shared with other
+/// files are copied before written, so pages are only written while no one
+/// else accesses them, except the mappings.
+#[derive(Default)]
+struct Content {
+    size: u64,
+    pages: BTreeMap<u64, PageSlot>,
+}
+
+impl Content {
+    /// Returns the page at `index` for writing, allocating it if absent, and
+    /// copying it if shared with other files.
+    fn page(&mut self, index: u64) -> &PageRef {
+        let slot = self.pages.entry(index).or_default();
+        if Arc::strong_count(slot) > 1 {
+            let page = FilePage::new();
+            // SAFETY: the new page is not shared yet.
+            unsafe { page.copy_from(slot) };
+            *slot = Arc::new(Arc::new(page));
+        }
+        slot
+    }
+
+    fn read(&self, offset: u64, buf: &mut [u8]) -> usize {
+        let len = buf.len().min(self.size.saturating_sub(offset) as usize);
+        let mut pos = 0;
+        while pos < len {
+            let cur = offset + pos as u64;
+            let page_off = (cur % PAGE_SIZE_U64) as usize;
+            let n = (PAGE_SIZE - page_off).min(len - pos);
+            let dst = &mut buf[pos..pos + n];
+            match self.pages.get(&(cur / PAGE_SIZE_U64)) {
+                Some(page) => {
+                    page.read(page_off, dst);
+                }
+                None => dst.fill(0),
+            }
+            pos += n;
+        }
+        len
+    }
+
+    fn write(&mut self, offset: u64, buf: &[u8]) {
+        let mut pos = 0;
+        while pos < buf.len() {
+            let cur = offset + pos as u64;
+            let page_off = (cur % PAGE_SIZE_U64) as usize;
+            let page = self.page(cur / PAGE_SIZE_U64);
+            // SAFETY: the page is not shared with other files, and the content
+            // is locked.
+            pos += unsafe { page.write(page_off, &buf[pos..]) };
+        }
+        self.size = self.size.max(offset + buf.len() as u64);
+    }
+
+    /// Returns the pages in `range` to be shared with another file. Pages that
+    /// may be mapped are copied instead.
+    fn share(&self, range: Range<u64>) -> Vec<(u64, PageSlot)> {
+        let share = |slot: &PageSlot| {
+            if is_mapped(slot) {
+                let page = FilePage::new();
+                // SAFETY: the new page is not shared yet.
+                unsafe { page.copy_from(slot) };
+                Arc::new(Arc::new(page))
+            } else {
+                slot.clone()
+            }
+        };
+        self.pages
+            .range(range)
+            .map(|(&index, slot)| (index, share(slot)))
+            .collect()
+    }
+
+    /// Copies `len` bytes of `src`, or of this content if `src` is `None`,
+    /// at `src_offset` to `offset`. Both ranges must be within the contents.
+    ///
+    /// If `share` is `true`, both offsets must be aligned to pages, and the
+    /// whole pages are shared with `src` instead of copied.
+    fn copy_from(
+        &mut self,
+        offset: u64,
+        src: Option<&Content>,
+        src_offset: u64,
+        len: u64,
+        share: bool,
+    ) {
+        let mut done = 0;
+        if share {
+            let (start, count) = (src_offset / PAGE_SIZE_U64, len / PAGE_SIZE_U64);
+            let pages = src.unwrap_or(self).share(start..start + count);
+            let dst_start = offset / PAGE_SIZE_U64;
+            self.remove_pages(dst_start..dst_start + count);
+            for (index, slot) in pages {
+                let index = index - start + dst_start;
+                match self.pages.get(&index) {
+                    // mapped pages are kept, and take the content instead
+                    // SAFETY: mapped pages are not shared with other files.
+                    Some(page) => unsafe { page.copy_from(&slot) },
+                    None => {
+                        self.pages.insert(index, slot);
+                    }
+                }
+            }
+            done = count * PAGE_SIZE_U64;
+        }
+        let mut buf = copy_buffer(len - done);
+        while done < len {
+            let n = (len - done).min(PAGE_SIZE_U64) as usize;
+            src.unwrap_or(self).read(src_offset + done, &mut buf[..n]);
+            self.write(offset + done, &buf[..n]);
+            done += n as u64;
+        }
+        self.size = self.size.max(offset + len);
+    }
+
+    /// Returns the number of bytes charged to the filesystem, which covers
+    /// the whole file and the pages allocated beyond the end of file.
+    fn usage(&self) -> u64 {
+        let allocated = self
+            .pages
+            .last_key_value()
+            .map_or(0, |(&index, _)| (index + 1) * PAGE_SIZE_U64);
+        self.size.max(allocated)
+    }
+
+    /// Removes the pages in `range`.
+    ///
+    /// Pages that may be mapped are zeroed and kept instead, so that the
+    /// mappings keep seeing the content of the file.
+    fn remove_pages(&mut self, range: Range<u64>) {
+        let mut removed = self.pages.split_off(&range.start);
+        self.pages.append(&mut removed.split_off(&range.end));
+        for (index, slot) in removed {
+            if is_mapped(&slot) {
+                // SAFETY: mapped pages are not shared with other files.
+                unsafe { slot.zero_from(0) };
+                self.pages.insert(index, slot);
+            }
+        }
+    }
+
+    /// Zeros the partial pages at both ends of `[offset, end)`, and returns
+    /// the indexes of the whole pages in between.
+    fn zero_partial(&mut self, offset: u64, end: u64) -> Range<u64> {
+        let mut zero = |pos: u64, len: u64| {
+            let index = pos / PAGE_SIZE_U64;
+            if self.pages.contains_key(&index) {
+                let page = self.page(index);
+                // SAFETY: the page is not shared with other files.
+                unsafe { page.zero((pos % PAGE_SIZE_U64) as usize, len as usize) };
+            }
+        };
+        let (first, last) = (offset.div_ceil(PAGE_SIZE_U64), end / PAGE_SIZE_U64);
+        if first > last {
+            // within a single page
+            zero(offset, end - offset);
+            return first..first;
+        }
+        if !offset.is_multiple_of(PAGE_SIZE_U64) {
+            zero(offset, PAGE_SIZE_U64);
+        }
+        if !end.is_multiple_of(PAGE_SIZE_U64) {
+            zero(last * PAGE_SIZE_U64, end % PAGE_SIZE_U64);
+        }
+        first..last
+    }
+
+    fn allocate(&mut self, offset: u64, end: u64) {
+        for index in offset / PAGE_SIZE_U64..end.div_ceil(PAGE_SIZE_U64) {
+            self.page(index);
+        }
+    }
+
+    fn punch_hole(&mut self, offset: u64, end: u64) {
+        let whole = self.zero_partial(offset, end);
+        self.remove_pages(whole);
+    }
+
+    fn zero_range(&mut self, offset: u64, end: u64) {
+        self.allocate(offset, end);
+        let whole = self.zero_partial(offset, end);
+        for index in whole {
+            // SAFETY: the page is not shared with other files.
+            unsafe { self.page(index).zero_from(0) };
+        }
+    }
+
+    /// Removes `[offset, end)`, which must be aligned to pages. No page from
+    /// `offset` on may be mapped.
+    fn collapse_range(&mut self, offset: u64, end: u64) {
+        let (start, shift) = (offset / PAGE_SIZE_U64, (end - offset) / PAGE_SIZE_U64);
+        let mut tail = self.pages.split_off(&start);
+        let tail = tail.split_off(&(start + shift));
+        self.pages
+            .extend(tail.into_iter().map(|(index, page)| (index - shift, page)));
+        self.size -= end - offset;
+    }
+
+    /// Inserts a hole at `[offset, end)`, which must be aligned to pages. No
+    /// page from `offset` on may be mapped.
+    fn insert_range(&mut self, offset: u64, end: u64) {
+        let (start, shift) = (offset / PAGE_SIZE_U64, (end - offset) / PAGE_SIZE_U64);
+        let tail = self.pages.split_off(&start);
+        self.pages
+            .extend(tail.into_iter().map(|(index, page)| (index + shift, page)));
+        self.size += end - offset;
+    }
+
+    /// Returns `true` if any page from `offset` on may be mapped.
+    fn is_mapped_from(&self, offset: u64) -> bool {
+        (self.pages)
+            .range(offset / PAGE_SIZE_U64..)
+            .any(|(_, slot)| is_mapped(slot))
+    }
+
+    fn seek_data(&self, offset: u64) -> Option<u64> {
+        let (start, _) = self.pages.range(offset / PAGE_SIZE_U64..).next()?;
+        let pos = offset.max(start * PAGE_SIZE_U64);
+        (pos < self.size).then_some(pos)
+    }
+
+    fn seek_hole(&self, offset: u64) -> Option<u64> {
+        if offset >= self.size {
+            return None;
+        }
+        let mut index = offset / PAGE_SIZE_U64;
+        for (&i, _) in self.pages.range(index..) {
+            if i != index {
+                break;
+            }
+            index += 1;
+        }
+        Some(offset.max(index * PAGE_SIZE_U64).min(self.size))
+    }
+
+    fn truncate(&mut self, size: u64) {
+        if size < self.size {
+            self.remove_pages(size.div_ceil(PAGE_SIZE_U64)..u64::MAX);
+        }
+        // zero the tail of the last page, and the pages between the old and
+        // the new end of file, which mappings may have written to
+        let (first, last) = (size.min(self.size), size.max(self.size));
+        let indexes: Vec<u64> = (self.pages)
+            .range(first / PAGE_SIZE_U64..last.div_ceil(PAGE_SIZE_U64))
+            .map(|(&index, _)| index)
+            .collect();
+        for index in indexes {
+            let start = first.max(index * PAGE_SIZE_U64);
+            let page = self.page(index);
+            // SAFETY: the page is not shared with other files.
+            unsafe { page.zero_from((start % PAGE_SIZE_U64) as usize) };
+        }
+        self.size = size;
+    }
+}
+
 /// The file node in the RAM filesystem.
 ///
 /// It implements [`axfs_vfs::VfsNodeOps`].
 pub struct FileNode {
-    content: RwLock<Vec<u8>>,
+    content: RwLock<Content>,
+    pub(super) nlink: AtomicU64,
+    pub(super) meta: NodeMeta,
 }
 
 impl FileNode {
-    pub(super) const fn new() -> Self {
+    pub(super) fn new(fs: &Arc<FsContext>, perm: VfsNodePerm) -> Self {
         Self {
-            content: RwLock::new(Vec::new()),
+            content: RwLock::new(Content::default()),
+            nlink: AtomicU64::new(1),
+            meta: NodeMeta::new(fs, perm),
         }
     }
+
+    /// Changes the content with `f`, charging the filesystem beforehand for
+    /// the content to grow up to `max_usage` bytes.
+    fn charge<T>(
+        &self,
+        content: &mut Content,
+        max_usage: u64,
+        f: impl FnOnce(&mut Content) -> T,
+    ) -> VfsResult<T> {
+        let fs = self.meta.fs();
+        let old = content.usage();
+        let max = old.max(max_usage);
+        fs.update_usage(old, max)?;
+        let ret = f(content);
+        // giving back the unused part never fails
+        fs.update_usage(max, content.usage()).ok();
+        Ok(ret)
+    }
 }
 
 impl VfsNodeOps for FileNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_file(self.content.read().len() as _, 0))
+        let content = self.content.read();
+        let size = content.size;
+        let blocks = content.usage().div_ceil(BLOCK_SIZE) * (BLOCK_SIZE / 512);
+        drop(content);
+        let mut attr = VfsNodeAttr::new_file(size, blocks);
+        attr.set_nlink(self.nlink.load(Ordering::Acquire));
+        self.meta.fill_attr(&mut attr);
+        Ok(attr)
+    }
+
+    fn set_attr(&self, attr: &VfsNodeAttr, mask: VfsAttrMask) -> VfsResult {
+        self.meta.set_attr(attr, mask);
+        Ok(())
+    }
+
+    fn get_xattr(&self, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.get_xattr(name, buf)
+    }
+
+    fn set_xattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> VfsResult {
+        self.meta.set_xattr(name, value, flags, true)
+    }
+
+    fn list_xattr(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.list_xattr(buf)
+    }
+
+    fn remove_xattr(&self, name: &str) -> VfsResult {
+        self.meta.remove_xattr(name)
+    }
+
+    fn lock_manager(&self) -> Option<&LockManager> {
+        Some(self.meta.locks())
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
         let mut content = self.content.write();
-        if size < content.len() as u64 {
-            content.truncate(size as _);
+        self.charge(&mut content, size, |content| content.truncate(size))?;
+        self.meta.touch_mtime();
+        Ok(())
+    }
+
+    fn fallocate(&self, mode: FallocateMode, offset: u64, len: u64) -> VfsResult {
+        let end = offset
+            .checked_add(len)
+            .filter(|_| len > 0)
+            .ok_or(VfsError::InvalidInput)?;
+        let keep_size = mode.contains(FallocateMode::KEEP_SIZE);
+        let mode = mode - FallocateMode::KEEP_SIZE;
+        let mut content = self.content.write();
+        let new_size = if keep_size {
+            content.size
+        } else {
+            content.size.max(end)
+        };
+
+        if mode.is_empty() {
+            self.charge(&mut content, end, |content| {
+                content.allocate(offset, end);
+                content.size = new_size;
+            })?;
+        } else if mode == FallocateMode::ZERO_RANGE {
+            self.charge(&mut content, end, |content| {
+                content.zero_range(offset, end);
+                content.size = new_size;
+            })?;
+        } else if mode == FallocateMode::PUNCH_HOLE && keep_size {
+            self.charge(&mut content, 0, |content| content.punch_hole(offset, end))?;
+        } else if mode == FallocateMode::COLLAPSE_RANGE || mode == FallocateMode::INSERT_RANGE {
+            let aligned = offset.is_multiple_of(PAGE_SIZE_U64) && len.is_multiple_of(PAGE_SIZE_U64);
+            if keep_size || !aligned {
+                return Err(VfsError::InvalidInput);
+            }
+            // moving mapped pages would change the file offsets they map
+            if content.is_mapped_from(offset) {
+                return Err(VfsError::ResourceBusy);
+            }
+            if mode == FallocateMode::COLLAPSE_RANGE {
+                // the range must not reach the end of file
+                if end >= content.size {
+                    return Err(VfsError::InvalidInput);
+                }
+                self.charge(&mut content, 0, |content| {
+                    content.collapse_range(offset, end)
+                })?;
+            } else {
+                if offset >= content.size || content.size.checked_add(len).is_none() {
+                    return Err(VfsError::InvalidInput);
+                }
+                let max_usage = content.usage() + len;
+                self.charge(&mut content, max_usage, |content| {
+                    content.insert_range(offset, end)
+                })?;
+            }
         } else {
-            content.resize(size as _, 0);
+            return Err(VfsError::OperationNotSupported);
         }
+        self.meta.touch_mtime();
         Ok(())
     }
 
+    fn seek_data(&self, offset: u64) -> VfsResult<Option<u64>> {
+        Ok(self.content.read().seek_data(offset))
+    }
+
+    fn seek_hole(&self, offset: u64) -> VfsResult<Option<u64>> {
+        Ok(self.content.read().seek_hole(offset))
+    }
+
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
-        let content = self.content.read();
-        let start = content.len().min(offset as usize);
-        let end = content.len().min(offset as usize + buf.len());
-        let src = &content[start..end];
-        buf[..src.len()].copy_from_slice(src);
-        Ok(src.len())
+        let len = self.content.read().read(offset, buf);
+        self.meta.touch_atime();
+        Ok(len)
     }
 
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
-        let offset = offset as usize;
+        let end = offset
+            .checked_add(buf.len() as u64)
+            .ok_or(VfsError::InvalidInput)?;
         let mut content = self.content.write();
-        if offset + buf.len() > content.len() {
-            content.resize(offset + buf.len(), 0);
-        }
-        let dst = &mut content[offset..offset + buf.len()];
-        dst.copy_from_slice(&buf[..dst.len()]);
+        self.charge(&mut content, end, |content| content.write(offset, buf))?;
+        self.meta.touch_mtime();
         Ok(buf.len())
     }
 
+    fn read_vectored_at(&self, offset: u64, bufs: &mut [&mut [u8]]) -> VfsResult<usize> {
+        let content = self.content.read();
+        let mut total = 0;
+        for buf in bufs.iter_mut() {
+            let len = content.read(offset + total as u64, buf);
+            total += len;
+            if len < buf.len() {
+                break;
+            }
+        }
+        self.meta.touch_atime();
+        Ok(total)
+    }
+
+    fn write_vectored_at(&self, offset: u64, bufs: &[&[u8]]) -> VfsResult<usize> {
+        let total = bufs.iter().map(|buf| buf.len() as u64).sum::<u64>();
+        let end = offset.checked_add(total).ok_or(VfsError::InvalidInput)?;
+        let mut content = self.content.write();
+        self.charge(&mut content, end, |content| {
+            let mut pos = offset;
+            for buf in bufs {
+                content.write(pos, buf);
+                pos += buf.len() as u64;
+            }
+        })?;
+        self.meta.touch_mtime();
+        Ok(total as usize)
+    }
+
+    fn append(&self, bufs: &[&[u8]]) -> VfsResult<(u64, usize)> {
+        let total = bufs.iter().map(|buf| buf.len() as u64).sum::<u64>();
+        let mut content = self.content.write();
+        let offset = content.size;
+        let end = offset.checked_add(total).ok_or(VfsError::InvalidInput)?;
+        self.charge(&mut content, end, |content| {
+            for buf in bufs {
+                content.write(content.size, buf);
+            }
+        })?;
+        self.meta.touch_mtime();
+        Ok((offset, total as usize))
+    }
+
+    fn copy_range(
+        &self,
+        offset: u64,
+        src: &VfsNodeRef,
+        src_offset: u64,
+        len: u64,
+    ) -> VfsResult<u64> {
+        let Some(src_file) = src.as_any().downcast_ref::<FileNode>() else {
+            return generic_copy_range(self, offset, src, src_offset, len);
+        };
+        if offset.checked_add(len).is_none() || src_offset.checked_add(len).is_none() {
+            return Err(VfsError::InvalidInput);
+        }
+        // copy-on-write clones are only made within the same filesystem
+        let share = Arc::ptr_eq(self.meta.fs(), src_file.meta.fs())
+            && offset.is_multiple_of(PAGE_SIZE_U64)
+            && src_offset.is_multiple_of(PAGE_SIZE_U64);
+
+        let len = if core::ptr::eq(self, src_file) {
+            if offset < src_offset + len && src_offset < offset + len {
+                return Err(VfsError::InvalidInput);
+            }
+            let mut content = self.content.write();
+            let len = len.min(content.size.saturating_sub(src_offset));
+            self.charge(&mut content, offset + len, |content| {
+                content.copy_from(offset, None, src_offset, len, share)
+            })?;
+            len
+        } else {
+            // lock in address order, so that copies in opposite directions
+            // do not deadlock
+            let (mut content, src_content) = if (self as *const Self) < (src_file as *const Self) {
+                let content = self.content.write();
+                (content, src_file.content.read())
+            } else {
+                let src_content = src_file.content.read();
+                (self.content.write(), src_content)
+            };
+            let len = len.min(src_content.size.saturating_sub(src_offset));
+            self.charge(&mut content, offset + len, |content| {
+                content.copy_from(offset, Some(&src_content), src_offset, len, share)
+            })?;
+            len
+        };
+        if len > 0 {
+            self.meta.touch_mtime();
+        }
+        src_file.meta.touch_atime();
+        Ok(len)
+    }
+
+    fn get_pages(&self, offset: u64, len: u64) -> VfsResult<Vec<PageRef>> {
+        if !offset.is_multiple_of(PAGE_SIZE_U64) || !len.is_multiple_of(PAGE_SIZE_U64) {
+            return Err(VfsError::InvalidInput);
+        }
+        let mut content = self.content.write();
+        let start = offset / PAGE_SIZE_U64;
+        let end = offset
+            .saturating_add(len)
+            .min(content.size.div_ceil(PAGE_SIZE_U64) * PAGE_SIZE_U64)
+            / PAGE_SIZE_U64;
+        Ok((start..end).map(|i| content.page(i).clone()).collect())
+    }
+
+    fn writeback_pages(&self, offset: u64, len: u64) -> VfsResult {
+        // the pages are the storage of the file, only the times are updated
+        let content = self.content.read();
+        let start = offset / PAGE_SIZE_U64;
+        let end = offset.saturating_add(len).div_ceil(PAGE_SIZE_U64);
+        let mut dirty = false;
+        for page in content.pages.range(start..end).map(|(_, page)| page) {
+            dirty |= page.clear_dirty();
+        }
+        if dirty {
+            self.meta.touch_mtime();
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn into_async(self: Arc<Self>) -> Option<axfs_vfs::AsyncVfsNodeRef> {
+        Some(self)
+    }
+
     impl_vfs_non_dir_default! {}
 }
+
+impl Drop for FileNode {
+    fn drop(&mut self) {
+        let usage = self.content.get_mut().usage();
+        self.meta.fs().update_usage(usage, 0).ok();
+    }
+}
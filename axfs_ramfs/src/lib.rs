@@ -6,19 +6,28 @@
 
 extern crate alloc;
 
+#[cfg(feature = "async")]
+mod async_ops;
 mod dir;
 mod file;
+mod meta;
+mod special;
+mod symlink;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::dir::DirNode;
 pub use self::file::FileNode;
+pub use self::special::SpecialNode;
+pub use self::symlink::SymlinkNode;
 
 use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodePerm, VfsNodeRef, VfsOps, VfsResult};
 use spin::once::Once;
 
+use self::meta::FsContext;
+
 /// A RAM filesystem that implements [`axfs_vfs::VfsOps`].
 pub struct RamFileSystem {
     parent: Once<VfsNodeRef>,
@@ -30,7 +39,20 @@ impl RamFileSystem {
     pub fn new() -> Self {
         Self {
             parent: Once::new(),
-            root: DirNode::new(None),
+            root: DirNode::new(None, &FsContext::new(None), VfsNodePerm::default_dir()),
+        }
+    }
+
+    /// Create a new instance, where the total size of file contents is
+    /// limited to `capacity` bytes.
+    pub fn with_capacity(capacity: u64) -> Self {
+        Self {
+            parent: Once::new(),
+            root: DirNode::new(
+                None,
+                &FsContext::new(Some(capacity)),
+                VfsNodePerm::default_dir(),
+            ),
         }
     }
 
@@ -50,9 +72,17 @@ impl VfsOps for RamFileSystem {
         Ok(())
     }
 
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        Ok(self.root.meta.fs().statfs())
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         self.root.clone()
     }
+
+    fn dev_id(&self) -> u64 {
+        self.root.meta.fs().dev()
+    }
 }
 
 impl Default for RamFileSystem {
@@ -0,0 +1,304 @@
+use alloc::collections::BTreeMap;
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use axfs_vfs::{alloc_anon_dev, current_time, FileSystemInfo, VfsAttrMask, VfsNodeAttr};
+use axfs_vfs::{LockManager, VfsError, VfsNodePerm, VfsResult};
+use axfs_vfs::{XattrFlags, XattrNamespace, NAME_MAX, XATTR_SIZE_MAX};
+use spin::{Mutex, MutexGuard, RwLock};
+
+/// Block size of the RAM filesystem reported in `statfs`.
+pub(crate) const BLOCK_SIZE: u64 = 4096;
+
+/// Number of free inodes reported in `statfs`. There is no limit on the
+/// number of nodes, so a large count is reported instead.
+const FREE_INODES: u64 = 1 << 32;
+
+/// Maximum total size of the names and values of extended attributes on a
+/// node.
+const XATTR_TOTAL_MAX: usize = 65536;
+
+/// `RAMFS_MAGIC` in Linux.
+const RAMFS_MAGIC: u64 = 0x8584_58f6;
+
+/// States shared by all nodes in the same RAM filesystem.
+pub(crate) struct FsContext {
+    dev: u64,
+    next_ino: AtomicU64,
+    nr_inodes: AtomicU64,
+    used_blocks: AtomicU64,
+    max_blocks: Option<u64>,
+    rename_lock: Mutex<()>,
+}
+
+impl FsContext {
+    pub fn new(capacity: Option<u64>) -> Arc<Self> {
+        Arc::new(Self {
+            dev: alloc_anon_dev(),
+            next_ino: AtomicU64::new(1),
+            nr_inodes: AtomicU64::new(0),
+            used_blocks: AtomicU64::new(0),
+            max_blocks: capacity.map(|capacity| capacity / BLOCK_SIZE),
+            rename_lock: Mutex::new(()),
+        })
+    }
+
+    /// Returns the device ID of the filesystem.
+    pub fn dev(&self) -> u64 {
+        self.dev
+    }
+
+    /// Locks the filesystem for a rename, so that the tree is not changed by
+    /// other renames while directories are being locked.
+    pub fn lock_rename(&self) -> MutexGuard<'_, ()> {
+        self.rename_lock.lock()
+    }
+
+    fn alloc_ino(&self) -> u64 {
+        self.nr_inodes.fetch_add(1, Ordering::Relaxed);
+        self.next_ino.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Accounts for a file growing or shrinking from `old_size` to `new_size`
+    /// bytes.
+    ///
+    /// Returns [`VfsError::StorageFull`] if the capacity is exceeded.
+    pub fn update_usage(&self, old_size: u64, new_size: u64) -> VfsResult {
+        let old_blocks = old_size.div_ceil(BLOCK_SIZE);
+        let new_blocks = new_size.div_ceil(BLOCK_SIZE);
+        if new_blocks <= old_blocks {
+            self.used_blocks
+                .fetch_sub(old_blocks - new_blocks, Ordering::Relaxed);
+            return Ok(());
+        }
+        let delta = new_blocks - old_blocks;
+        self.used_blocks
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                let new_used = used.checked_add(delta)?;
+                match self.max_blocks {
+                    Some(max) if new_used > max => None,
+                    _ => Some(new_used),
+                }
+            })
+            .map_err(|_| VfsError::StorageFull)?;
+        Ok(())
+    }
+
+    /// Returns the attributes of the filesystem.
+    ///
+    /// Without a capacity limit, no block counts are reported, like
+    /// `simple_statfs` in Linux, as the filesystem can grow as long as there
+    /// is free memory.
+    pub fn statfs(&self) -> FileSystemInfo {
+        let mut info = FileSystemInfo::default();
+        info.fs_type = RAMFS_MAGIC;
+        info.block_size = BLOCK_SIZE;
+        if let Some(max_blocks) = self.max_blocks {
+            let free_blocks = max_blocks.saturating_sub(self.used_blocks.load(Ordering::Relaxed));
+            info.blocks = max_blocks;
+            info.blocks_free = free_blocks;
+            info.blocks_available = free_blocks;
+        }
+        info.files = self.nr_inodes.load(Ordering::Relaxed) + FREE_INODES;
+        info.files_free = FREE_INODES;
+        info.fsid = self.dev;
+        info.name_max = NAME_MAX as _;
+        info
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Inner {
+    perm: VfsNodePerm,
+    uid: u32,
+    gid: u32,
+    atime: Duration,
+    mtime: Duration,
+    ctime: Duration,
+    btime: Duration,
+}
+
+/// Metadata shared by all kinds of nodes in the RAM filesystem.
+pub(crate) struct NodeMeta {
+    fs: Arc<FsContext>,
+    ino: u64,
+    inner: RwLock<Inner>,
+    xattrs: RwLock<BTreeMap<String, Vec<u8>>>,
+    locks: LockManager,
+}
+
+impl NodeMeta {
+    pub fn new(fs: &Arc<FsContext>, perm: VfsNodePerm) -> Self {
+        let now = current_time();
+        Self {
+            fs: fs.clone(),
+            ino: fs.alloc_ino(),
+            inner: RwLock::new(Inner {
+                perm,
+                uid: 0,
+                gid: 0,
+                atime: now,
+                mtime: now,
+                ctime: now,
+                btime: now,
+            }),
+            xattrs: RwLock::new(BTreeMap::new()),
+            locks: LockManager::new(),
+        }
+    }
+
+    /// Returns the filesystem that the node belongs to.
+    pub fn fs(&self) -> &Arc<FsContext> {
+        &self.fs
+    }
+
+    /// Returns the inode number of the node.
+    pub fn ino(&self) -> u64 {
+        self.ino
+    }
+
+    /// Returns the advisory lock manager of the node.
+    pub fn locks(&self) -> &LockManager {
+        &self.locks
+    }
+
+    /// Fills the metadata into `attr`.
+    pub fn fill_attr(&self, attr: &mut VfsNodeAttr) {
+        attr.set_dev(self.fs.dev);
+        attr.set_ino(self.ino);
+        let inner = *self.inner.read();
+        attr.set_perm(inner.perm);
+        attr.set_uid(inner.uid);
+        attr.set_gid(inner.gid);
+        attr.set_atime(inner.atime);
+        attr.set_mtime(inner.mtime);
+        attr.set_ctime(inner.ctime);
+        attr.set_btime(inner.btime);
+    }
+
+    /// Updates the access time, called when the content is read.
+    pub fn touch_atime(&self) {
+        self.inner.write().atime = current_time();
+    }
+
+    /// Updates the modification and change time, called when the content is
+    /// modified.
+    pub fn touch_mtime(&self) {
+        let now = current_time();
+        let mut inner = self.inner.write();
+        inner.mtime = now;
+        inner.ctime = now;
+    }
+
+    /// Updates the change time, called when the metadata is modified.
+    pub fn touch_ctime(&self) {
+        self.inner.write().ctime = current_time();
+    }
+
+    /// Sets the fields selected by `mask` from `attr`, and updates the change
+    /// time.
+    pub fn set_attr(&self, attr: &VfsNodeAttr, mask: VfsAttrMask) {
+        let now = current_time();
+        let mut inner = self.inner.write();
+        if mask.contains(VfsAttrMask::MODE) {
+            inner.perm = attr.perm();
+        }
+        if mask.contains(VfsAttrMask::UID) {
+            inner.uid = attr.uid();
+        }
+        if mask.contains(VfsAttrMask::GID) {
+            inner.gid = attr.gid();
+        }
+        if mask.contains(VfsAttrMask::ATIME) {
+            inner.atime = attr.atime();
+        }
+        if mask.contains(VfsAttrMask::MTIME) {
+            inner.mtime = attr.mtime();
+        }
+        inner.ctime = now;
+    }
+
+    /// Gets the value of the extended attribute `name` into `buf`.
+    pub fn get_xattr(&self, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        XattrNamespace::parse(name)?;
+        let xattrs = self.xattrs.read();
+        let value = xattrs.get(name).ok_or(VfsError::NotFound)?;
+        copy_out(value, buf)
+    }
+
+    /// Sets the value of the extended attribute `name`.
+    ///
+    /// `user.*` attributes are only allowed if `allow_user` is `true`.
+    pub fn set_xattr(
+        &self,
+        name: &str,
+        value: &[u8],
+        flags: XattrFlags,
+        allow_user: bool,
+    ) -> VfsResult {
+        match XattrNamespace::parse(name)?.0 {
+            XattrNamespace::User if !allow_user => return Err(VfsError::OperationNotPermitted),
+            XattrNamespace::System => return Err(VfsError::OperationNotSupported),
+            _ => {}
+        }
+        if value.len() > XATTR_SIZE_MAX {
+            return Err(VfsError::ArgumentListTooLong);
+        }
+
+        let mut xattrs = self.xattrs.write();
+        let old = xattrs.get(name);
+        if old.is_some() && flags.contains(XattrFlags::CREATE) {
+            return Err(VfsError::AlreadyExists);
+        }
+        if old.is_none() && flags.contains(XattrFlags::REPLACE) {
+            return Err(VfsError::NotFound);
+        }
+        let total = xattrs
+            .iter()
+            .filter(|(n, _)| n.as_str() != name)
+            .map(|(n, v)| n.len() + v.len())
+            .sum::<usize>();
+        if total + name.len() + value.len() > XATTR_TOTAL_MAX {
+            return Err(VfsError::StorageFull);
+        }
+        xattrs.insert(name.into(), value.into());
+        drop(xattrs);
+        self.touch_ctime();
+        Ok(())
+    }
+
+    /// Lists the names of all extended attributes into `buf`.
+    pub fn list_xattr(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut names = Vec::new();
+        for name in self.xattrs.read().keys() {
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        copy_out(&names, buf)
+    }
+
+    /// Removes the extended attribute `name`.
+    pub fn remove_xattr(&self, name: &str) -> VfsResult {
+        XattrNamespace::parse(name)?;
+        self.xattrs.write().remove(name).ok_or(VfsError::NotFound)?;
+        self.touch_ctime();
+        Ok(())
+    }
+}
+
+/// Copies `data` into `buf`, or only returns its size if `buf` is empty.
+fn copy_out(data: &[u8], buf: &mut [u8]) -> VfsResult<usize> {
+    if buf.is_empty() {
+        return Ok(data.len());
+    }
+    let dst = buf.get_mut(..data.len()).ok_or(VfsError::OutOfRange)?;
+    dst.copy_from_slice(data);
+    Ok(data.len())
+}
+
+impl Drop for NodeMeta {
+    fn drop(&mut self) {
+        self.fs.nr_inodes.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,79 @@
+use alloc::sync::Arc;
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{VfsAttrMask, VfsNodePerm, VfsNodeType, XattrFlags};
+
+use crate::meta::{FsContext, NodeMeta};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The special file node in the RAM filesystem, which is a character
+/// device, a block device, a FIFO or a socket.
+///
+/// It only stores the type and the device ID. Opening it is up to the kernel,
+/// which usually finds the driver by [`rdev()`](SpecialNode::rdev).
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct SpecialNode {
+    ty: VfsNodeType,
+    rdev: u64,
+    pub(super) nlink: AtomicU64,
+    pub(super) meta: NodeMeta,
+}
+
+impl SpecialNode {
+    pub(super) fn new(fs: &Arc<FsContext>, ty: VfsNodeType, perm: VfsNodePerm, rdev: u64) -> Self {
+        Self {
+            ty,
+            rdev,
+            nlink: AtomicU64::new(1),
+            meta: NodeMeta::new(fs, perm),
+        }
+    }
+
+    /// Returns the type of the node.
+    pub fn node_type(&self) -> VfsNodeType {
+        self.ty
+    }
+
+    /// Returns the device ID of the node, which is 0 for FIFOs and sockets.
+    pub fn rdev(&self) -> u64 {
+        self.rdev
+    }
+}
+
+impl VfsNodeOps for SpecialNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let mut attr = VfsNodeAttr::new(VfsNodePerm::default_file(), self.ty, 0, 0);
+        attr.set_rdev(self.rdev);
+        attr.set_nlink(self.nlink.load(Ordering::Acquire));
+        self.meta.fill_attr(&mut attr);
+        Ok(attr)
+    }
+
+    fn set_attr(&self, attr: &VfsNodeAttr, mask: VfsAttrMask) -> VfsResult {
+        self.meta.set_attr(attr, mask);
+        Ok(())
+    }
+
+    fn get_xattr(&self, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.get_xattr(name, buf)
+    }
+
+    fn set_xattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> VfsResult {
+        self.meta.set_xattr(name, value, flags, false)
+    }
+
+    fn list_xattr(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.list_xattr(buf)
+    }
+
+    fn remove_xattr(&self, name: &str) -> VfsResult {
+        self.meta.remove_xattr(name)
+    }
+
+    #[cfg(feature = "async")]
+    fn into_async(self: Arc<Self>) -> Option<axfs_vfs::AsyncVfsNodeRef> {
+        Some(self)
+    }
+
+    impl_vfs_non_dir_default! {}
+}
@@ -0,0 +1,83 @@
+use alloc::{string::String, sync::Arc};
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{VfsAttrMask, VfsError, VfsNodePerm, VfsNodeType, XattrFlags};
+
+use crate::meta::{FsContext, NodeMeta};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The symbolic link node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct SymlinkNode {
+    target: String,
+    pub(super) nlink: AtomicU64,
+    pub(super) meta: NodeMeta,
+}
+
+impl SymlinkNode {
+    pub(super) fn new(fs: &Arc<FsContext>, target: &str) -> Self {
+        Self {
+            target: target.into(),
+            nlink: AtomicU64::new(1),
+            meta: NodeMeta::new(fs, VfsNodePerm::default_symlink()),
+        }
+    }
+
+    /// Returns the path that the symbolic link points to.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let mut attr = VfsNodeAttr::new(
+            VfsNodePerm::default_symlink(),
+            VfsNodeType::SymLink,
+            self.target.len() as _,
+            0,
+        );
+        attr.set_nlink(self.nlink.load(Ordering::Acquire));
+        self.meta.fill_attr(&mut attr);
+        Ok(attr)
+    }
+
+    fn set_attr(&self, attr: &VfsNodeAttr, mask: VfsAttrMask) -> VfsResult {
+        if mask.contains(VfsAttrMask::MODE) {
+            // the permission of a symbolic link is never used
+            return Err(VfsError::OperationNotSupported);
+        }
+        self.meta.set_attr(attr, mask);
+        Ok(())
+    }
+
+    fn get_xattr(&self, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.get_xattr(name, buf)
+    }
+
+    fn set_xattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> VfsResult {
+        self.meta.set_xattr(name, value, flags, false)
+    }
+
+    fn list_xattr(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        self.meta.list_xattr(buf)
+    }
+
+    fn remove_xattr(&self, name: &str) -> VfsResult {
+        self.meta.remove_xattr(name)
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let len = self.target.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.target.as_bytes()[..len]);
+        self.meta.touch_atime();
+        Ok(len)
+    }
+
+    #[cfg(feature = "async")]
+    fn into_async(self: Arc<Self>) -> Option<axfs_vfs::AsyncVfsNodeRef> {
+        Some(self)
+    }
+
+    impl_vfs_non_dir_default! {}
+}
@@ -134,3 +134,1657 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+#[test]
+fn test_symlink() {
+    // .
+    // ├── foo
+    // │   ├── f1
+    // │   ├── l1 -> f1
+    // │   └── l2 -> ../l3
+    // ├── l3 -> /foo
+    // ├── l4 -> l4
+    // └── l5 -> nonexistent
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/f1", VfsNodeType::File).unwrap();
+    root.symlink("foo/l1", "f1").unwrap();
+    root.symlink("foo/l2", "../l3").unwrap();
+    root.symlink("l3", "/foo").unwrap();
+    root.symlink("l4", "l4").unwrap();
+    root.symlink("l5", "nonexistent").unwrap();
+    assert_eq!(
+        root.symlink("l3", "foo").err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(root.symlink("l6", "").err(), Some(VfsError::NotFound));
+
+    let f1 = root.clone().lookup("foo/f1").unwrap();
+    let foo = root.clone().lookup("foo").unwrap();
+    let l1 = root.clone().lookup_nofollow("foo/l1").unwrap();
+    assert!(l1.get_attr().unwrap().is_symlink());
+    assert_eq!(l1.get_attr().unwrap().size(), 2);
+    let mut buf = [0; 16];
+    assert_eq!(l1.readlink(&mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], b"f1");
+    assert_eq!(f1.readlink(&mut buf).err(), Some(VfsError::InvalidInput));
+
+    assert!(Arc::ptr_eq(&root.clone().lookup("foo/l1").unwrap(), &f1));
+    assert!(Arc::ptr_eq(&root.clone().lookup("l3").unwrap(), &foo));
+    assert!(Arc::ptr_eq(&root.clone().lookup("l3/").unwrap(), &foo));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup_nofollow("l3/").unwrap(),
+        &foo
+    ));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup_nofollow("foo/l2/l2/l1").unwrap(),
+        &l1
+    ));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("foo/l2/l2/l1").unwrap(),
+        &f1
+    ));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("l3/../foo").unwrap(),
+        &foo
+    ));
+    assert_eq!(
+        root.clone().lookup("foo/l1/").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.clone().lookup("l4").err(),
+        Some(VfsError::FilesystemLoop)
+    );
+    assert!(root.clone().lookup_nofollow("l4").is_ok());
+    assert_eq!(root.clone().lookup("l5").err(), Some(VfsError::NotFound));
+
+    root.create("l3/f2", VfsNodeType::File).unwrap();
+    root.symlink("foo/l2/l6", "f2").unwrap();
+    assert!(root.clone().lookup("foo/l6").is_ok());
+    assert_eq!(root.remove("l3/f2"), Ok(()));
+    assert_eq!(root.remove("l3/l6"), Ok(()));
+    assert_eq!(root.remove("foo/l1"), Ok(()));
+    assert!(root.clone().lookup("foo/f1").is_ok());
+}
+
+#[test]
+fn test_path_resolver() {
+    use axfs_vfs::{PathResolver, SYMLOOP_MAX};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/f1", VfsNodeType::File).unwrap();
+    root.symlink("l0", "foo").unwrap();
+    for i in 1..SYMLOOP_MAX {
+        root.symlink(&format!("l{i}"), &format!("l{}", i - 1))
+            .unwrap();
+    }
+    let foo = root.clone().lookup("foo").unwrap();
+    let f1 = root.clone().lookup("foo/f1").unwrap();
+
+    // deep paths are walked without recursion
+    let deep = "foo/..//./".repeat(10000) + "foo/f1";
+    assert!(Arc::ptr_eq(&root.clone().lookup(&deep).unwrap(), &f1));
+    let last = format!("l{}", SYMLOOP_MAX - 1);
+    assert!(Arc::ptr_eq(&root.clone().lookup(&last).unwrap(), &foo));
+    root.symlink("l_max", &last).unwrap();
+    assert_eq!(
+        root.clone().lookup("l_max").err(),
+        Some(VfsError::FilesystemLoop)
+    );
+
+    let resolver = PathResolver::new().with_max_symlinks(2);
+    assert!(Arc::ptr_eq(
+        &resolver.resolve(&root, "l1/f1", true).unwrap(),
+        &f1
+    ));
+    assert_eq!(
+        resolver.resolve(&root, "l2/f1", true).err(),
+        Some(VfsError::FilesystemLoop)
+    );
+    assert!(resolver.resolve(&root, "l2", false).is_ok());
+
+    // `..` and absolute paths stay within the given root
+    let resolver = PathResolver::new().with_root(foo.clone());
+    assert!(Arc::ptr_eq(
+        &resolver.resolve(&foo, "../../f1", true).unwrap(),
+        &f1
+    ));
+    assert!(Arc::ptr_eq(
+        &resolver.resolve(&root, "/f1", true).unwrap(),
+        &f1
+    ));
+    assert_eq!(
+        resolver.resolve(&root, "foo/f1/", true).err(),
+        Some(VfsError::NotADirectory)
+    );
+
+    // `.` and `..` can not be resolved in a file
+    let resolver = PathResolver::new();
+    for path in ["foo/f1/..", "foo/f1/.", "l0/f1/../f1"] {
+        assert_eq!(
+            resolver.resolve(&root, path, true).err(),
+            Some(VfsError::NotADirectory)
+        );
+    }
+    assert_eq!(
+        root.clone().lookup("foo/f1/..").err(),
+        Some(VfsError::NotADirectory)
+    );
+
+    let (dir, name) = PathResolver::new()
+        .resolve_parent(&root, "l0/new//")
+        .unwrap();
+    assert!(Arc::ptr_eq(&dir, &foo));
+    assert_eq!(name, "new");
+    let (dir, name) = PathResolver::new().resolve_parent(&foo, "/f1").unwrap();
+    assert!(Arc::ptr_eq(&dir, &root));
+    assert_eq!(name, "f1");
+}
+
+#[test]
+fn test_hard_link() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/bar", VfsNodeType::Dir).unwrap();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.symlink("l1", "f1").unwrap();
+    assert_eq!(root.get_attr().unwrap().nlink(), 3);
+    assert_eq!(
+        root.clone()
+            .lookup("foo/bar")
+            .unwrap()
+            .get_attr()
+            .unwrap()
+            .nlink(),
+        2
+    );
+
+    let f1 = root.clone().lookup("f1").unwrap();
+    assert_eq!(f1.get_attr().unwrap().nlink(), 1);
+    root.link("foo/f2", &f1).unwrap();
+    root.link("foo/bar/../f3", &f1).unwrap();
+    assert_eq!(f1.get_attr().unwrap().nlink(), 3);
+    assert_eq!(
+        root.link("foo/f2", &f1).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(f1.get_attr().unwrap().nlink(), 3);
+
+    let foo = root.clone().lookup("foo").unwrap();
+    assert_eq!(
+        root.link("baz", &foo).err(),
+        Some(VfsError::OperationNotPermitted)
+    );
+    let l1 = root.clone().lookup_nofollow("l1").unwrap();
+    root.link("l2", &l1).unwrap();
+    assert_eq!(l1.get_attr().unwrap().nlink(), 2);
+    assert!(Arc::ptr_eq(&root.clone().lookup("l2").unwrap(), &f1));
+
+    let f2 = root.clone().lookup("foo/f2").unwrap();
+    assert!(Arc::ptr_eq(&f1, &f2));
+    assert_eq!(f2.write_at(0, b"hello").unwrap(), 5);
+    let mut buf = [0; 5];
+    assert_eq!(
+        root.clone().lookup("foo/f3").unwrap().read_at(0, &mut buf),
+        Ok(5)
+    );
+    assert_eq!(&buf, b"hello");
+
+    assert_eq!(root.remove("f1"), Ok(()));
+    assert_eq!(root.remove("foo/f2"), Ok(()));
+    assert_eq!(f1.get_attr().unwrap().nlink(), 1);
+    assert_eq!(root.remove("foo/f3"), Ok(()));
+    assert_eq!(f1.get_attr().unwrap().nlink(), 0);
+    // the data stays alive until the last handle is dropped
+    assert_eq!(f1.read_at(0, &mut buf), Ok(5));
+    assert_eq!(&buf, b"hello");
+    assert_eq!(
+        root.clone().lookup("foo/f3").err(),
+        Some(VfsError::NotFound)
+    );
+}
+
+#[test]
+fn test_timestamps() {
+    use core::sync::atomic::{AtomicU64, Ordering};
+    use core::time::Duration;
+
+    static NOW: AtomicU64 = AtomicU64::new(100);
+    fn now() -> Duration {
+        Duration::from_secs(NOW.fetch_add(1, Ordering::SeqCst))
+    }
+    axfs_vfs::set_clock_source(now);
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let attr = f1.get_attr().unwrap();
+    assert!(attr.btime() >= Duration::from_secs(100));
+    assert_eq!(attr.atime(), attr.btime());
+    assert_eq!(attr.mtime(), attr.btime());
+    assert_eq!(attr.ctime(), attr.btime());
+    let dir_mtime = root.get_attr().unwrap().mtime();
+    assert!(dir_mtime >= attr.btime());
+
+    f1.write_at(0, b"hello").unwrap();
+    let attr2 = f1.get_attr().unwrap();
+    assert!(attr2.mtime() > attr.mtime());
+    assert_eq!(attr2.ctime(), attr2.mtime());
+    assert_eq!(attr2.atime(), attr.atime());
+
+    f1.read_at(0, &mut [0; 5]).unwrap();
+    let attr3 = f1.get_attr().unwrap();
+    assert!(attr3.atime() > attr2.mtime());
+    assert_eq!(attr3.mtime(), attr2.mtime());
+
+    f1.truncate(1).unwrap();
+    assert!(f1.get_attr().unwrap().mtime() > attr3.atime());
+
+    f1.set_times(Some(Duration::from_secs(1)), None).unwrap();
+    let attr4 = f1.get_attr().unwrap();
+    assert_eq!(attr4.atime(), Duration::from_secs(1));
+    assert_eq!(attr4.btime(), attr.btime());
+    assert!(attr4.ctime() > attr4.mtime());
+
+    root.remove("f1").unwrap();
+    assert!(root.get_attr().unwrap().mtime() > dir_mtime);
+    assert!(f1.get_attr().unwrap().ctime() > attr4.ctime());
+}
+
+#[test]
+fn test_set_attr() {
+    use axfs_vfs::{VfsAttrMask, VfsNodePerm};
+    use core::time::Duration;
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/f1", VfsNodeType::File).unwrap();
+    root.symlink("l1", "foo").unwrap();
+
+    let f1 = root.clone().lookup("foo/f1").unwrap();
+    let mut attr = f1.get_attr().unwrap();
+    assert_eq!(attr.perm().mode(), 0o666);
+    assert_eq!((attr.uid(), attr.gid()), (0, 0));
+
+    attr.set_perm(VfsNodePerm::from_bits_truncate(0o640));
+    attr.set_uid(1000);
+    attr.set_gid(100);
+    attr.set_mtime(Duration::from_secs(42));
+    f1.set_attr(&attr, VfsAttrMask::MODE | VfsAttrMask::UID)
+        .unwrap();
+    let new_attr = f1.get_attr().unwrap();
+    assert_eq!(new_attr.perm().mode(), 0o640);
+    assert_eq!((new_attr.uid(), new_attr.gid()), (1000, 0));
+    assert_ne!(new_attr.mtime(), Duration::from_secs(42));
+
+    f1.set_attr(&attr, VfsAttrMask::GID | VfsAttrMask::MTIME)
+        .unwrap();
+    let new_attr = f1.get_attr().unwrap();
+    assert_eq!((new_attr.uid(), new_attr.gid()), (1000, 100));
+    assert_eq!(new_attr.mtime(), Duration::from_secs(42));
+
+    let foo = root.clone().lookup("foo").unwrap();
+    let mut attr = foo.get_attr().unwrap();
+    assert_eq!(attr.perm().mode(), 0o755);
+    attr.set_perm(VfsNodePerm::from_bits_truncate(0o700));
+    foo.set_attr(&attr, VfsAttrMask::MODE).unwrap();
+    assert_eq!(
+        root.clone()
+            .lookup("l1")
+            .unwrap()
+            .get_attr()
+            .unwrap()
+            .perm()
+            .mode(),
+        0o700
+    );
+
+    let l1 = root.clone().lookup_nofollow("l1").unwrap();
+    let mut attr = l1.get_attr().unwrap();
+    assert_eq!(attr.perm().mode(), 0o777);
+    attr.set_uid(1000);
+    assert_eq!(
+        l1.set_attr(&attr, VfsAttrMask::MODE).err(),
+        Some(VfsError::OperationNotSupported)
+    );
+    l1.set_attr(&attr, VfsAttrMask::UID).unwrap();
+    assert_eq!(l1.get_attr().unwrap().uid(), 1000);
+}
+
+#[test]
+fn test_inode_number() {
+    use axfs_vfs::VfsDirEntry;
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/f1", VfsNodeType::File).unwrap();
+    root.symlink("l1", "foo/f1").unwrap();
+    let f1 = root.clone().lookup("foo/f1").unwrap();
+    root.link("f2", &f1).unwrap();
+
+    let dev = ramfs.dev_id();
+    let attrs = ["", "foo", "foo/f1", "l1", "f2"].map(|path| {
+        root.clone()
+            .lookup_nofollow(path)
+            .unwrap()
+            .get_attr()
+            .unwrap()
+    });
+    assert!(attrs.iter().all(|attr| attr.dev() == dev));
+    assert_eq!(attrs[2].ino(), attrs[4].ino());
+    let mut inos = attrs.map(|attr| attr.ino());
+    inos.sort();
+    assert_eq!(inos[..4], [1, 2, 3, 3]);
+    assert!(inos[4] > 3);
+
+    let mut dirents: [VfsDirEntry; 5] = core::array::from_fn(|_| VfsDirEntry::default());
+    assert_eq!(root.read_dir(0, &mut dirents), Ok(5));
+    // `..` of the root directory refers to itself
+    assert_eq!(dirents[1].ino(), attrs[0].ino());
+    for ent in dirents.iter().skip(2) {
+        let name = core::str::from_utf8(ent.name_as_bytes()).unwrap();
+        let node = root.clone().lookup_nofollow(name).unwrap();
+        assert_eq!(ent.ino(), node.get_attr().unwrap().ino());
+    }
+    let foo = root.clone().lookup("foo").unwrap();
+    assert_eq!(foo.read_dir(1, &mut dirents[..1]), Ok(1));
+    assert_eq!(dirents[0].ino(), attrs[0].ino());
+
+    let ramfs2 = RamFileSystem::new();
+    assert_ne!(ramfs2.dev_id(), dev);
+    assert_eq!(ramfs2.root_dir().get_attr().unwrap().ino(), 1);
+    assert_eq!(
+        ramfs2.root_dir().link("f1", &f1).err(),
+        Some(VfsError::CrossesDevices)
+    );
+}
+
+#[test]
+fn test_statfs() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    let info = ramfs.statfs().unwrap();
+    assert_eq!(info.fs_type, 0x858458f6);
+    assert_eq!(info.block_size, 4096);
+    assert_eq!(
+        (info.blocks, info.blocks_free, info.blocks_available),
+        (0, 0, 0)
+    );
+    assert!(info.files_free > 0);
+    assert_eq!(info.files - info.files_free, 1);
+    assert_eq!(info.fsid, ramfs.dev_id());
+    assert_eq!(info.name_max, 255);
+
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    f1.write_at(4000, &[1; 100]).unwrap();
+    assert_eq!(f1.get_attr().unwrap().blocks(), 16);
+    let used_files = || {
+        let info = ramfs.statfs().unwrap();
+        info.files - info.files_free
+    };
+    let info = ramfs.statfs().unwrap();
+    assert_eq!((info.blocks, info.blocks_free), (0, 0));
+    assert_eq!(used_files(), 3);
+
+    root.remove("f1").unwrap();
+    assert_eq!(used_files(), 3);
+    drop(f1);
+    assert_eq!(used_files(), 2);
+
+    let ramfs = RamFileSystem::with_capacity(3 * 4096);
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    assert_eq!(f1.write_at(0, &[1; 4097]), Ok(4097));
+    let info = ramfs.statfs().unwrap();
+    assert_eq!(
+        (info.blocks, info.blocks_free, info.blocks_available),
+        (3, 1, 1)
+    );
+    assert_eq!(
+        f1.write_at(8192, &[1; 4097]).err(),
+        Some(VfsError::StorageFull)
+    );
+    assert_eq!(f1.truncate(3 * 4096 + 1).err(), Some(VfsError::StorageFull));
+    assert_eq!(f1.get_attr().unwrap().size(), 4097);
+    assert_eq!(f1.truncate(3 * 4096), Ok(()));
+    assert_eq!(ramfs.statfs().unwrap().blocks_free, 0);
+    f1.truncate(10).unwrap();
+    assert_eq!(ramfs.statfs().unwrap().blocks_free, 2);
+}
+
+#[test]
+fn test_xattr() {
+    use axfs_vfs::XattrFlags;
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.symlink("l1", "f1").unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let mut buf = [0; 64];
+
+    assert_eq!(f1.list_xattr(&mut []), Ok(0));
+    assert_eq!(f1.get_xattr("user.foo", &mut buf), Err(VfsError::NotFound));
+    f1.set_xattr("user.foo", b"bar", XattrFlags::empty())
+        .unwrap();
+    f1.set_xattr(
+        "security.selinux",
+        b"system_u:object_r:tmp_t",
+        XattrFlags::CREATE,
+    )
+    .unwrap();
+    assert_eq!(f1.get_xattr("user.foo", &mut []), Ok(3));
+    assert_eq!(f1.get_xattr("user.foo", &mut buf), Ok(3));
+    assert_eq!(&buf[..3], b"bar");
+    assert_eq!(
+        f1.get_xattr("security.selinux", &mut buf[..4]),
+        Err(VfsError::OutOfRange)
+    );
+
+    assert_eq!(f1.list_xattr(&mut []), Ok(26));
+    assert_eq!(f1.list_xattr(&mut buf), Ok(26));
+    assert_eq!(&buf[..26], b"security.selinux\0user.foo\0");
+    assert_eq!(f1.list_xattr(&mut buf[..10]), Err(VfsError::OutOfRange));
+
+    assert_eq!(
+        f1.set_xattr("user.foo", b"baz", XattrFlags::CREATE),
+        Err(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        f1.set_xattr("user.new", b"baz", XattrFlags::REPLACE),
+        Err(VfsError::NotFound)
+    );
+    f1.set_xattr("user.foo", b"bazz", XattrFlags::REPLACE)
+        .unwrap();
+    assert_eq!(f1.get_xattr("user.foo", &mut buf), Ok(4));
+    assert_eq!(&buf[..4], b"bazz");
+
+    // namespaces and limits
+    assert_eq!(
+        f1.set_xattr("foo.bar", b"", XattrFlags::empty()),
+        Err(VfsError::OperationNotSupported)
+    );
+    assert_eq!(
+        f1.set_xattr("user.", b"", XattrFlags::empty()),
+        Err(VfsError::OutOfRange)
+    );
+    assert_eq!(
+        f1.set_xattr("system.posix_acl_access", b"", XattrFlags::empty()),
+        Err(VfsError::OperationNotSupported)
+    );
+    let long_name = "user.".to_string() + &"x".repeat(251);
+    assert_eq!(
+        f1.set_xattr(&long_name, b"", XattrFlags::empty()),
+        Err(VfsError::OutOfRange)
+    );
+    assert_eq!(
+        f1.set_xattr("user.big", &[0; 65537], XattrFlags::empty()),
+        Err(VfsError::ArgumentListTooLong)
+    );
+    f1.set_xattr("security.big", &[0; 65000], XattrFlags::empty())
+        .unwrap();
+    assert_eq!(
+        f1.set_xattr("security.big2", &[0; 1000], XattrFlags::empty()),
+        Err(VfsError::StorageFull)
+    );
+    f1.set_xattr("security.big", &[0; 100], XattrFlags::empty())
+        .unwrap();
+    f1.set_xattr("security.big2", &[0; 1000], XattrFlags::empty())
+        .unwrap();
+
+    let l1 = root.clone().lookup_nofollow("l1").unwrap();
+    assert_eq!(
+        l1.set_xattr("user.foo", b"bar", XattrFlags::empty()),
+        Err(VfsError::OperationNotPermitted)
+    );
+    assert_eq!(
+        l1.set_xattr("trusted.foo", b"bar", XattrFlags::empty()),
+        Err(VfsError::OperationNotSupported)
+    );
+    assert_eq!(
+        l1.get_xattr("trusted.foo", &mut buf),
+        Err(VfsError::OperationNotSupported)
+    );
+    l1.set_xattr("security.foo", b"bar", XattrFlags::empty())
+        .unwrap();
+    assert_eq!(l1.list_xattr(&mut buf), Ok(13));
+
+    assert_eq!(f1.remove_xattr("user.foo"), Ok(()));
+    assert_eq!(f1.remove_xattr("user.foo"), Err(VfsError::NotFound));
+    assert_eq!(f1.get_xattr("user.foo", &mut buf), Err(VfsError::NotFound));
+    root.set_xattr("user.dir", b"1", XattrFlags::empty())
+        .unwrap();
+    assert_eq!(root.get_xattr("user.dir", &mut buf), Ok(1));
+}
+
+#[test]
+fn test_file_lock() {
+    use axfs_vfs::{FileLock, FileLockKind::*};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let locks = f1.lock_manager().unwrap();
+
+    // flock
+    locks.flock(1, Shared, None).unwrap();
+    locks.flock(2, Shared, None).unwrap();
+    assert_eq!(locks.flock(1, Exclusive, None), Err(VfsError::WouldBlock));
+    locks.funlock(2);
+    locks.flock(1, Exclusive, None).unwrap();
+    assert_eq!(locks.flock(2, Shared, None), Err(VfsError::WouldBlock));
+    locks.funlock(1);
+    locks.flock(2, Exclusive, None).unwrap();
+
+    // POSIX locks do not interact with flock
+    let lock = |kind, start, len, owner| FileLock::new(kind, start, len, owner).unwrap();
+    locks.lock(lock(Exclusive, 0, 100, 1), None).unwrap();
+    locks.lock(lock(Shared, 100, 100, 2), None).unwrap();
+    locks.lock(lock(Shared, 150, 0, 3), None).unwrap();
+    assert_eq!(
+        locks.lock(lock(Shared, 50, 60, 2), None),
+        Err(VfsError::WouldBlock)
+    );
+    assert_eq!(
+        locks.test_lock(&lock(Exclusive, 99, 10, 3)),
+        Some(lock(Exclusive, 0, 100, 1))
+    );
+    assert_eq!(
+        locks.test_lock(&lock(Exclusive, 160, 10, 3)),
+        Some(lock(Shared, 100, 100, 2))
+    );
+    assert_eq!(locks.test_lock(&lock(Shared, 120, 0, 3)), None);
+    assert_eq!(locks.test_lock(&lock(Exclusive, 20, 10, 1)), None);
+    assert!(FileLock::new(Shared, u64::MAX - 1, 2, 1).is_err());
+
+    // splitting and merging locks of the same owner
+    locks.unlock(1, 40, 20).unwrap();
+    assert_eq!(locks.test_lock(&lock(Exclusive, 40, 20, 2)), None);
+    assert!(locks.test_lock(&lock(Shared, 39, 1, 2)).is_some());
+    assert!(locks.test_lock(&lock(Shared, 60, 1, 2)).is_some());
+    locks.lock(lock(Shared, 10, 10, 1), None).unwrap();
+    locks.lock(lock(Shared, 200, 10, 1), None).unwrap();
+    assert!(locks.test_lock(&lock(Shared, 0, 40, 2)).is_some());
+    assert_eq!(locks.test_lock(&lock(Shared, 10, 10, 2)), None);
+    locks.lock(lock(Exclusive, 40, 20, 1), None).unwrap();
+    locks.unlock(2, 0, 0).unwrap();
+    locks.unlock(3, 0, 0).unwrap();
+    assert_eq!(
+        locks.test_lock(&lock(Shared, 0, 0, 2)),
+        Some(lock(Exclusive, 0, 10, 1))
+    );
+    locks.unlock_all(1);
+    assert_eq!(locks.test_lock(&lock(Exclusive, 0, 0, 2)), None);
+}
+
+#[test]
+fn test_file_lock_wait() {
+    use axfs_vfs::{FileLock, FileLockKind::*};
+    use std::task::{Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+
+    let lock = FileLock::new(Exclusive, 0, 0, 1).unwrap();
+    f1.lock_manager().unwrap().lock(lock, None).unwrap();
+    let f1_clone = f1.clone();
+    let waiter = thread::spawn(move || {
+        // a blocking request, as `F_SETLKW` does
+        let lock = FileLock::new(Shared, 10, 10, 2).unwrap();
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        loop {
+            match f1_clone.lock_manager().unwrap().lock(lock, Some(&waker)) {
+                Err(VfsError::WouldBlock) => thread::park(),
+                ret => return ret,
+            }
+        }
+    });
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert!(!waiter.is_finished());
+    f1.lock_manager().unwrap().unlock_all(1);
+    assert_eq!(waiter.join().unwrap(), Ok(()));
+}
+
+#[test]
+fn test_mmap_pages() {
+    use axfs_vfs::PAGE_SIZE;
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let mut buf = [0; 8];
+
+    // a sparse file with data in the first and third pages
+    f1.write_at(10, b"hello").unwrap();
+    f1.write_at(2 * PAGE_SIZE as u64 + 100, b"world").unwrap();
+    assert_eq!(f1.get_attr().unwrap().size(), 2 * PAGE_SIZE as u64 + 105);
+    assert_eq!(f1.read_at(PAGE_SIZE as u64, &mut buf), Ok(8));
+    assert_eq!(buf, [0; 8]);
+
+    assert_eq!(
+        f1.get_pages(1, PAGE_SIZE as _).err(),
+        Some(VfsError::InvalidInput)
+    );
+    assert_eq!(f1.get_pages(0, 100).err(), Some(VfsError::InvalidInput));
+    // the range is limited to the end of file
+    let pages = f1.get_pages(0, 8 * PAGE_SIZE as u64).unwrap();
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[0].as_ptr() as usize % PAGE_SIZE, 0);
+    assert_eq!(pages[0].read(10, &mut buf[..5]), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(pages[2].read(100, &mut buf[..5]), 5);
+    assert_eq!(&buf[..5], b"world");
+
+    // pages are shared with the file in both directions
+    unsafe { pages[1].as_ptr().add(3).write(b'x') };
+    pages[1].set_dirty();
+    assert_eq!(f1.read_at(PAGE_SIZE as u64 + 3, &mut buf[..1]), Ok(1));
+    assert_eq!(buf[0], b'x');
+    f1.write_at(11, b"EL").unwrap();
+    assert_eq!(pages[0].read(10, &mut buf[..5]), 5);
+    assert_eq!(&buf[..5], b"hELlo");
+    let again = f1.get_pages(PAGE_SIZE as _, PAGE_SIZE as _).unwrap();
+    assert!(Arc::ptr_eq(&again[0], &pages[1]));
+
+    assert!(pages[1].is_dirty());
+    f1.writeback_pages(0, 2 * PAGE_SIZE as u64).unwrap();
+    assert!(!pages[1].is_dirty());
+
+    // truncation zeroes the tail and the mapped pages beyond
+    f1.truncate(12).unwrap();
+    assert_eq!(pages[0].read(10, &mut buf[..5]), 5);
+    assert_eq!(&buf[..5], b"hE\0\0\0");
+    assert_eq!(pages[2].read(100, &mut buf[..5]), 5);
+    assert_eq!(&buf[..5], [0; 5]);
+    f1.truncate(3 * PAGE_SIZE as u64).unwrap();
+    assert_eq!(f1.read_at(2 * PAGE_SIZE as u64 + 100, &mut buf[..5]), Ok(5));
+    assert_eq!(&buf[..5], [0; 5]);
+
+    assert_eq!(root.get_pages(0, 0).err(), Some(VfsError::Unsupported));
+}
+
+#[test]
+fn test_mmap_coherence() {
+    use axfs_vfs::{FallocateMode, PAGE_SIZE};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("f2", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let f2 = root.clone().lookup("f2").unwrap();
+    let page = PAGE_SIZE as u64;
+    let mut buf = [0; 4];
+
+    f1.write_at(0, &[1; 3 * PAGE_SIZE]).unwrap();
+    let pages = f1.get_pages(0, 3 * page).unwrap();
+
+    // the mapping stays the storage of the file after truncation
+    f1.truncate(10).unwrap();
+    assert_eq!(pages[1].read(0, &mut buf), 4);
+    assert_eq!(buf, [0; 4]);
+    unsafe { pages[1].as_ptr().write(b'x') };
+    f1.truncate(2 * page).unwrap();
+    assert_eq!(f1.read_at(page, &mut buf), Ok(4));
+    assert_eq!(buf, [0; 4]);
+    f1.write_at(page + 1, b"ab").unwrap();
+    assert_eq!(pages[1].read(0, &mut buf), 4);
+    assert_eq!(buf, *b"\0ab\0");
+
+    // and after punching a hole
+    f1.fallocate(
+        FallocateMode::PUNCH_HOLE | FallocateMode::KEEP_SIZE,
+        0,
+        page,
+    )
+    .unwrap();
+    assert_eq!(pages[0].read(0, &mut buf), 4);
+    assert_eq!(buf, [0; 4]);
+    f1.write_at(0, b"cd").unwrap();
+    assert_eq!(pages[0].read(0, &mut buf), 4);
+    assert_eq!(buf, *b"cd\0\0");
+
+    // and after copying pages into it
+    f2.write_at(0, b"efgh").unwrap();
+    f2.truncate(2 * page).unwrap();
+    assert_eq!(f1.copy_range(0, &f2, 0, 2 * page), Ok(2 * page));
+    assert_eq!(pages[0].read(0, &mut buf), 4);
+    assert_eq!(&buf, b"efgh");
+    assert_eq!(pages[1].read(0, &mut buf), 4);
+    assert_eq!(buf, [0; 4]);
+    // the pages are not shared with `f2`
+    f1.write_at(0, b"ij").unwrap();
+    assert_eq!(f2.read_at(0, &mut buf), Ok(4));
+    assert_eq!(&buf, b"efgh");
+    assert_eq!(pages[0].read(0, &mut buf), 4);
+    assert_eq!(&buf, b"ijgh");
+    assert_eq!(pages[0].read(PAGE_SIZE + 1, &mut buf), 0);
+
+    // mapped pages are not moved to other offsets
+    for mode in [FallocateMode::COLLAPSE_RANGE, FallocateMode::INSERT_RANGE] {
+        assert_eq!(f1.fallocate(mode, 0, page), Err(VfsError::ResourceBusy));
+    }
+    drop(pages);
+    f1.fallocate(FallocateMode::COLLAPSE_RANGE, 0, page)
+        .unwrap();
+    assert_eq!(f1.get_attr().unwrap().size(), page);
+}
+
+#[test]
+fn test_file_handle() {
+    use axfs_vfs::{OpenFlags, SeekFrom, VfsDirEntry, VfsFile};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("d1", VfsNodeType::Dir).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let d1 = root.clone().lookup("d1").unwrap();
+    let mut buf = [0; 16];
+
+    let file = VfsFile::open(f1.clone(), OpenFlags::RDWR).unwrap();
+    assert_eq!(file.write(b"hello"), Ok(5));
+    assert_eq!(file.write(b" world"), Ok(6));
+    assert_eq!(file.offset(), 11);
+    assert_eq!(file.read(&mut buf), Ok(0));
+    assert_eq!(file.seek(SeekFrom::Start(6)), Ok(6));
+    assert_eq!(file.read(&mut buf), Ok(5));
+    assert_eq!(&buf[..5], b"world");
+    assert_eq!(file.seek(SeekFrom::Current(-5)), Ok(6));
+    assert_eq!(file.seek(SeekFrom::End(-11)), Ok(0));
+    assert_eq!(
+        file.seek(SeekFrom::Current(-1)),
+        Err(VfsError::InvalidInput)
+    );
+    assert_eq!(file.offset(), 0);
+    assert_eq!(file.read_at(6, &mut buf[..2]), Ok(2));
+    assert_eq!(file.offset(), 0);
+
+    // access modes
+    let rdonly = VfsFile::open(f1.clone(), OpenFlags::empty()).unwrap();
+    assert_eq!(rdonly.write(b"x"), Err(VfsError::BadFileDescriptor));
+    assert_eq!(rdonly.truncate(0), Err(VfsError::BadFileDescriptor));
+    assert_eq!(rdonly.read(&mut buf), Ok(11));
+    let wronly = VfsFile::open(f1.clone(), OpenFlags::WRONLY).unwrap();
+    assert_eq!(wronly.read(&mut buf), Err(VfsError::BadFileDescriptor));
+
+    // append
+    let append = VfsFile::open(f1.clone(), OpenFlags::WRONLY | OpenFlags::APPEND).unwrap();
+    assert_eq!(append.write(b"!"), Ok(1));
+    assert_eq!(append.offset(), 12);
+    assert_eq!(file.write(b"HELLO"), Ok(5));
+    assert_eq!(append.write_at(0, b"?"), Ok(1));
+    assert_eq!(file.get_attr().unwrap().size(), 13);
+    append.set_flags(OpenFlags::RDWR | OpenFlags::NONBLOCK);
+    assert_eq!(append.flags(), OpenFlags::WRONLY | OpenFlags::NONBLOCK);
+    assert!(append.is_nonblocking());
+    append.seek(SeekFrom::Start(0)).unwrap();
+    assert_eq!(append.write(b"h"), Ok(1));
+    assert_eq!(rdonly.read_at(0, &mut buf), Ok(13));
+    assert_eq!(&buf[..13], b"hELLO world!?");
+
+    // truncate and directories
+    let trunc = VfsFile::open(f1.clone(), OpenFlags::WRONLY | OpenFlags::TRUNC).unwrap();
+    assert_eq!(trunc.get_attr().unwrap().size(), 0);
+    assert_eq!(
+        VfsFile::open(f1.clone(), OpenFlags::DIRECTORY).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        VfsFile::open(d1.clone(), OpenFlags::RDWR).err(),
+        Some(VfsError::IsADirectory)
+    );
+    let dir = VfsFile::open(root.clone(), OpenFlags::DIRECTORY).unwrap();
+    let mut dirents: [VfsDirEntry; 3] = core::array::from_fn(|_| VfsDirEntry::default());
+    assert_eq!(dir.read_dir(&mut dirents), Ok(3));
+    assert_eq!(dir.read_dir(&mut dirents), Ok(1));
+    assert_eq!(dir.read_dir(&mut dirents), Ok(0));
+}
+
+#[test]
+fn test_file_append() {
+    use axfs_vfs::{OpenFlags, VfsFile};
+
+    const CHUNK: usize = 16;
+    const COUNT: usize = 200;
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    f1.write_at(0, b"head").unwrap();
+
+    // two open file descriptions appending to the same node
+    let writers: Vec<_> = [b'a', b'b']
+        .into_iter()
+        .map(|byte| {
+            let file = VfsFile::open(f1.clone(), OpenFlags::WRONLY | OpenFlags::APPEND).unwrap();
+            std::thread::spawn(move || {
+                for i in 0..COUNT {
+                    let chunk = [byte; CHUNK];
+                    if i % 2 == 0 {
+                        assert_eq!(file.write(&chunk), Ok(CHUNK));
+                    } else {
+                        let (a, b) = chunk.split_at(CHUNK / 2);
+                        assert_eq!(file.write_vectored(&[a, b]), Ok(CHUNK));
+                    }
+                    // the offset is after the data just written
+                    let mut buf = [0; CHUNK];
+                    let offset = file.offset() - CHUNK as u64;
+                    assert_eq!(file.node().read_at(offset, &mut buf), Ok(CHUNK));
+                    assert_eq!(buf, chunk);
+                }
+            })
+        })
+        .collect();
+    writers.into_iter().for_each(|w| w.join().unwrap());
+
+    let size = 4 + 2 * COUNT * CHUNK;
+    assert_eq!(f1.get_attr().unwrap().size(), size as u64);
+    let mut data = vec![0; size];
+    assert_eq!(f1.read_at(0, &mut data), Ok(size));
+    assert_eq!(&data[..4], b"head");
+    for chunk in data[4..].chunks(CHUNK) {
+        assert!(chunk.iter().all(|&b| b == chunk[0]));
+    }
+    assert_eq!(f1.append(&[b"tail"]), Ok((size as u64, 4)));
+}
+
+#[test]
+fn test_permission() {
+    use axfs_vfs::{AccessMode, Credentials, VfsAttrMask, VfsNodePerm};
+
+    // .
+    // ├── home (rwxr-x---, 1000:100)
+    // │   └── f1 (rw-r-----, 1000:100)
+    // ├── l1 -> home/f1
+    // └── l2 -> /home
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("home", VfsNodeType::Dir).unwrap();
+    root.create("home/f1", VfsNodeType::File).unwrap();
+    root.symlink("l1", "home/f1").unwrap();
+    root.symlink("l2", "/home").unwrap();
+
+    let set_owner = |path: &str, mode: u16| {
+        let node = root.clone().lookup(path).unwrap();
+        let mut attr = node.get_attr().unwrap();
+        attr.set_perm(VfsNodePerm::from_bits_truncate(mode));
+        attr.set_uid(1000);
+        attr.set_gid(100);
+        let mask = VfsAttrMask::MODE | VfsAttrMask::UID | VfsAttrMask::GID;
+        node.set_attr(&attr, mask).unwrap();
+    };
+    set_owner("home/f1", 0o640);
+    set_owner("home", 0o750);
+
+    let owner = Credentials::new(1000, 1000);
+    let member = Credentials::new(1001, 1001).with_groups(&[100]);
+    let other = Credentials::new(1002, 1002);
+    let root_cred = Credentials::root();
+
+    for cred in [&owner, &member, &root_cred] {
+        let f1 = root.clone().lookup_as("home/f1", cred, true).unwrap();
+        assert_eq!(f1.get_attr().unwrap().uid(), 1000);
+        assert!(root.clone().lookup_as("l1", cred, true).is_ok());
+        assert!(root.clone().lookup_as("l2/f1", cred, false).is_ok());
+    }
+    assert_eq!(
+        root.clone().lookup_as("home/f1", &other, true).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    // the target of a symbolic link is checked too
+    assert_eq!(
+        root.clone().lookup_as("l1", &other, true).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert!(root.clone().lookup_as("l1", &other, false).is_ok());
+    assert!(root.clone().lookup_as("home", &other, true).is_ok());
+    assert_eq!(
+        root.clone()
+            .lookup_as("l2/", &other, true)
+            .unwrap()
+            .get_attr()
+            .unwrap()
+            .uid(),
+        1000
+    );
+    assert_eq!(
+        root.clone().lookup_as("home/..", &other, true).err(),
+        Some(VfsError::PermissionDenied)
+    );
+
+    let f1 = root.clone().lookup("home/f1").unwrap().get_attr().unwrap();
+    assert!(owner
+        .check_access(&f1, AccessMode::READ | AccessMode::WRITE)
+        .is_ok());
+    assert!(member.check_access(&f1, AccessMode::READ).is_ok());
+    assert!(member.check_access(&f1, AccessMode::WRITE).is_err());
+    assert!(other.check_access(&f1, AccessMode::READ).is_err());
+    assert!(root_cred.check_access(&f1, AccessMode::WRITE).is_ok());
+    assert!(root_cred.check_access(&f1, AccessMode::EXECUTE).is_err());
+    // the owner class is used even if others have more permissions
+    let mut attr = f1;
+    attr.set_perm(VfsNodePerm::from_bits_truncate(0o077));
+    assert!(owner.check_access(&attr, AccessMode::READ).is_err());
+    assert!(member.check_access(&attr, AccessMode::EXECUTE).is_ok());
+}
+
+#[test]
+fn test_long_name() {
+    use axfs_vfs::{VfsDirEntry, NAME_MAX};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    let max_name = "a".repeat(NAME_MAX);
+    let long_name = "b".repeat(NAME_MAX + 1);
+
+    root.create(&max_name, VfsNodeType::File).unwrap();
+    root.create(&"d".repeat(100), VfsNodeType::Dir).unwrap();
+    let node = root.clone().lookup(&max_name).unwrap();
+
+    assert_eq!(
+        root.create(&long_name, VfsNodeType::File),
+        Err(VfsError::NameTooLong)
+    );
+    assert_eq!(
+        root.create(
+            &format!("{}/{long_name}", "d".repeat(100)),
+            VfsNodeType::Dir
+        ),
+        Err(VfsError::NameTooLong)
+    );
+    assert_eq!(
+        root.symlink(&long_name, &max_name),
+        Err(VfsError::NameTooLong)
+    );
+    assert_eq!(root.link(&long_name, &node), Err(VfsError::NameTooLong));
+    assert_eq!(
+        root.clone().lookup(&long_name).err(),
+        Some(VfsError::NameTooLong)
+    );
+
+    let mut dirents: [VfsDirEntry; 4] = core::array::from_fn(|_| VfsDirEntry::default());
+    assert_eq!(root.read_dir(0, &mut dirents), Ok(4));
+    assert_eq!(dirents[2].name_as_bytes(), max_name.as_bytes());
+    assert_eq!(dirents[3].name_as_bytes(), "d".repeat(100).as_bytes());
+    assert_eq!(ramfs.statfs().unwrap().name_max, NAME_MAX as u64);
+
+    // entries do not reserve space for the longest names
+    assert!(core::mem::size_of::<VfsDirEntry>() < 64);
+    // names are truncated at a character boundary
+    let ent = VfsDirEntry::new(&"\u{e9}".repeat(NAME_MAX), VfsNodeType::File);
+    assert_eq!(
+        ent.name_as_bytes(),
+        "\u{e9}".repeat(NAME_MAX / 2).as_bytes()
+    );
+}
+
+#[test]
+fn test_read_dir_cookie() {
+    use axfs_vfs::{OpenFlags, SeekFrom, VfsDirEntry, VfsFile};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    for i in 0..10 {
+        root.create(&format!("f{i}"), VfsNodeType::File).unwrap();
+    }
+
+    let name_of = |ent: &VfsDirEntry| String::from_utf8(ent.name_as_bytes().to_vec()).unwrap();
+    let mut dirents: [VfsDirEntry; 3] = core::array::from_fn(|_| VfsDirEntry::default());
+    let mut names = Vec::new();
+    let mut cookie = 0;
+    let mut round = 0;
+    loop {
+        let n = root.read_dir(cookie, &mut dirents).unwrap();
+        if n == 0 {
+            break;
+        }
+        names.extend(dirents[..n].iter().map(name_of));
+        cookie = dirents[n - 1].offset();
+        // modify the directory between calls
+        match round {
+            0 => {
+                root.remove("f0").unwrap(); // already read
+                root.remove("f5").unwrap(); // not read yet
+                root.create("a0", VfsNodeType::File).unwrap();
+            }
+            1 => {
+                root.remove("f9").unwrap();
+                root.create("f9", VfsNodeType::File).unwrap();
+            }
+            _ => {}
+        }
+        round += 1;
+    }
+
+    assert_eq!(&names[..2], [".", ".."]);
+    // entries present during the whole listing are returned exactly once
+    for i in [1, 2, 3, 4, 6, 7, 8] {
+        let name = format!("f{i}");
+        assert_eq!(names.iter().filter(|n| **n == name).count(), 1);
+    }
+    assert!(!names.contains(&"f5".into()));
+    assert!(names.iter().filter(|n| *n == "f9").count() <= 1);
+
+    // directory offsets of a file are cookies too
+    let dir = VfsFile::open(root.clone(), OpenFlags::empty()).unwrap();
+    assert_eq!(dir.read_dir(&mut dirents), Ok(3));
+    let pos = dir.offset();
+    let third = name_of(&dirents[2]);
+    assert_eq!(dir.read_dir(&mut dirents[..1]), Ok(1));
+    let fourth = name_of(&dirents[0]);
+    root.remove(&third).unwrap();
+    dir.seek(SeekFrom::Start(pos)).unwrap();
+    assert_eq!(dir.read_dir(&mut dirents[..1]), Ok(1));
+    assert_eq!(name_of(&dirents[0]), fourth);
+}
+
+#[test]
+fn test_vectored_io() {
+    use axfs_vfs::{OpenFlags, VfsFile};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+
+    assert_eq!(f1.write_vectored_at(2, &[b"hello", b"", b" world"]), Ok(11));
+    assert_eq!(f1.get_attr().unwrap().size(), 13);
+    let (mut a, mut b, mut c) = ([0; 4], [0; 0], [0; 16]);
+    assert_eq!(
+        f1.read_vectored_at(0, &mut [&mut a, &mut b, &mut c]),
+        Ok(13)
+    );
+    assert_eq!(&a, b"\0\0he");
+    assert_eq!(&c[..9], b"llo world");
+    // stops at the end of file
+    let (mut a, mut c) = ([0; 4], [0; 4]);
+    assert_eq!(f1.read_vectored_at(10, &mut [&mut a, &mut c]), Ok(3));
+    assert_eq!(&a[..3], b"rld");
+
+    let file = VfsFile::open(f1.clone(), OpenFlags::RDWR | OpenFlags::APPEND).unwrap();
+    assert_eq!(file.write_vectored(&[b"!", b"?"]), Ok(2));
+    assert_eq!(file.offset(), 15);
+    let mut buf = [0; 2];
+    assert_eq!(file.read_vectored(&mut [&mut buf]), Ok(0));
+    assert_eq!(file.read_at(13, &mut buf), Ok(2));
+    assert_eq!(&buf, b"!?");
+}
+
+#[test]
+fn test_fallocate() {
+    use axfs_vfs::{FallocateMode, OpenFlags, SeekFrom, VfsFile};
+    const PAGE: u64 = 4096;
+
+    let ramfs = RamFileSystem::with_capacity(8 * PAGE);
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let blocks_free = || ramfs.statfs().unwrap().blocks_free;
+
+    // preallocation beyond the end of file is charged but not visible
+    f1.fallocate(FallocateMode::KEEP_SIZE, 0, 2 * PAGE).unwrap();
+    assert_eq!(f1.get_attr().unwrap().size(), 0);
+    assert_eq!(f1.get_attr().unwrap().blocks(), 16);
+    assert_eq!(blocks_free(), 6);
+    assert_eq!(f1.seek_data(0), Ok(None));
+    f1.fallocate(FallocateMode::empty(), 0, 3 * PAGE).unwrap();
+    assert_eq!(f1.get_attr().unwrap().size(), 3 * PAGE);
+    assert_eq!(blocks_free(), 5);
+    assert_eq!(
+        f1.fallocate(FallocateMode::empty(), 0, 9 * PAGE),
+        Err(VfsError::StorageFull)
+    );
+    assert_eq!(
+        f1.fallocate(FallocateMode::empty(), 0, 0),
+        Err(VfsError::InvalidInput)
+    );
+
+    // punching a hole keeps the size, and the hole reads as zeros
+    f1.write_at(0, &[1; 3 * PAGE as usize]).unwrap();
+    let punch = FallocateMode::PUNCH_HOLE | FallocateMode::KEEP_SIZE;
+    assert_eq!(
+        f1.fallocate(FallocateMode::PUNCH_HOLE, 0, PAGE),
+        Err(VfsError::OperationNotSupported)
+    );
+    f1.fallocate(punch, PAGE - 10, PAGE + 20).unwrap();
+    assert_eq!(f1.get_attr().unwrap().size(), 3 * PAGE);
+    let mut buf = [0xff; 40];
+    f1.read_at(PAGE - 20, &mut buf).unwrap();
+    assert_eq!((&buf[..10], &buf[10..]), (&[1; 10][..], &[0; 30][..]));
+    f1.read_at(2 * PAGE - 20, &mut buf).unwrap();
+    assert_eq!((&buf[..30], &buf[30..]), (&[0; 30][..], &[1; 10][..]));
+    assert_eq!(f1.seek_data(0), Ok(Some(0)));
+    assert_eq!(f1.seek_hole(0), Ok(Some(PAGE)));
+    assert_eq!(f1.seek_data(PAGE), Ok(Some(2 * PAGE)));
+    assert_eq!(f1.seek_hole(2 * PAGE + 1), Ok(Some(3 * PAGE)));
+    assert_eq!(f1.seek_hole(3 * PAGE), Ok(None));
+
+    // zeroing a range keeps it allocated
+    f1.fallocate(FallocateMode::ZERO_RANGE, PAGE, PAGE + 20)
+        .unwrap();
+    assert_eq!(f1.seek_hole(0), Ok(Some(3 * PAGE)));
+    f1.read_at(2 * PAGE + 19, &mut buf[..3]).unwrap();
+    assert_eq!(&buf[..3], &[0, 1, 1]);
+
+    // collapse and insert whole pages
+    f1.write_at(0, b"first").unwrap();
+    f1.write_at(2 * PAGE, b"third").unwrap();
+    assert_eq!(
+        f1.fallocate(FallocateMode::COLLAPSE_RANGE, 1, PAGE),
+        Err(VfsError::InvalidInput)
+    );
+    assert_eq!(
+        f1.fallocate(FallocateMode::COLLAPSE_RANGE, PAGE, 2 * PAGE),
+        Err(VfsError::InvalidInput)
+    );
+    f1.fallocate(FallocateMode::COLLAPSE_RANGE, 0, PAGE)
+        .unwrap();
+    assert_eq!(f1.get_attr().unwrap().size(), 2 * PAGE);
+    f1.read_at(PAGE, &mut buf[..5]).unwrap();
+    assert_eq!(&buf[..5], b"third");
+    f1.fallocate(FallocateMode::INSERT_RANGE, 0, 2 * PAGE)
+        .unwrap();
+    assert_eq!(f1.get_attr().unwrap().size(), 4 * PAGE);
+    assert_eq!(f1.seek_data(0), Ok(Some(2 * PAGE)));
+    f1.read_at(3 * PAGE, &mut buf[..5]).unwrap();
+    assert_eq!(&buf[..5], b"third");
+
+    let file = VfsFile::open(f1.clone(), OpenFlags::empty()).unwrap();
+    assert_eq!(file.seek(SeekFrom::Data(1)), Ok(2 * PAGE));
+    assert_eq!(file.seek(SeekFrom::Hole(1)), Ok(1));
+    assert_eq!(
+        file.seek(SeekFrom::Data(4 * PAGE)),
+        Err(VfsError::NoSuchDevice)
+    );
+    assert_eq!(file.offset(), 1);
+    assert_eq!(
+        file.fallocate(FallocateMode::empty(), 0, 1),
+        Err(VfsError::BadFileDescriptor)
+    );
+}
+
+#[test]
+fn test_copy_range() {
+    const PAGE: u64 = 4096;
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("f2", VfsNodeType::File).unwrap();
+    root.create("dir", VfsNodeType::Dir).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let f2 = root.clone().lookup("f2").unwrap();
+    f1.write_at(0, &[1; PAGE as usize]).unwrap();
+    f1.write_at(2 * PAGE, &[2; 100]).unwrap();
+
+    // cloned pages keep the holes, and stop at the end of the source
+    assert_eq!(f2.copy_range(0, &f1, 0, 10 * PAGE), Ok(2 * PAGE + 100));
+    assert_eq!(f2.get_attr().unwrap().size(), 2 * PAGE + 100);
+    assert_eq!(f2.seek_hole(0), Ok(Some(PAGE)));
+    assert_eq!(f2.seek_data(PAGE), Ok(Some(2 * PAGE)));
+    let mut buf = [0; 4];
+    f2.read_at(2 * PAGE + 98, &mut buf).unwrap();
+    assert_eq!(&buf[..2], &[2, 2]);
+
+    // writes to either file are not seen by the other
+    f2.write_at(0, b"f2").unwrap();
+    f1.write_at(2 * PAGE, b"f1").unwrap();
+    f1.read_at(0, &mut buf).unwrap();
+    assert_eq!(&buf, &[1; 4]);
+    f2.read_at(2 * PAGE, &mut buf).unwrap();
+    assert_eq!(&buf, &[2; 4]);
+
+    // mapped pages are copied, not shared
+    let page = f1.get_pages(0, PAGE).unwrap().remove(0);
+    assert_eq!(f2.copy_range(PAGE, &f1, 0, PAGE), Ok(PAGE));
+    unsafe { page.write(0, b"mapped") };
+    f2.read_at(PAGE, &mut buf).unwrap();
+    assert_eq!(&buf, &[1; 4]);
+
+    // unaligned copies, within the same file and from another filesystem
+    assert_eq!(f1.copy_range(10, &f1, 2 * PAGE, 4), Ok(4));
+    f1.read_at(8, &mut buf).unwrap();
+    assert_eq!(&buf, b"\x01\x01f1");
+    assert_eq!(f1.copy_range(0, &f1, 10, 20), Err(VfsError::InvalidInput));
+    let other = RamFileSystem::new();
+    other.root_dir().create("f3", VfsNodeType::File).unwrap();
+    let f3 = other.root_dir().lookup("f3").unwrap();
+    assert_eq!(f3.copy_range(1, &f2, 0, 3), Ok(3));
+    f3.read_at(0, &mut buf).unwrap();
+    assert_eq!(&buf, b"\0f2\x01");
+    assert_eq!(f3.copy_range(0, &f2, 3 * PAGE, 1), Ok(0));
+
+    let dir = root.clone().lookup("dir").unwrap();
+    assert_eq!(f3.copy_range(0, &dir, 0, 1), Err(VfsError::IsADirectory));
+    assert_eq!(
+        f3.copy_range(u64::MAX, &f2, 0, 2),
+        Err(VfsError::InvalidInput)
+    );
+}
+
+#[test]
+fn test_rename() {
+    use axfs_vfs::RenameFlags;
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("a", VfsNodeType::Dir).unwrap();
+    root.create("a/b", VfsNodeType::Dir).unwrap();
+    root.create("a/f1", VfsNodeType::File).unwrap();
+    root.create("f2", VfsNodeType::File).unwrap();
+    root.create("d", VfsNodeType::Dir).unwrap();
+    root.create("d/f3", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("a/f1").unwrap();
+    let f2 = root.clone().lookup("f2").unwrap();
+    f1.write_at(0, b"f1").unwrap();
+
+    // within and across directories
+    root.rename("a/f1", "a/g1").unwrap();
+    assert_eq!(root.clone().lookup("a/f1").err(), Some(VfsError::NotFound));
+    root.rename("/a/g1", "/a/b/h1").unwrap();
+    assert!(Arc::ptr_eq(&root.clone().lookup("a/b/h1").unwrap(), &f1));
+    assert_eq!(
+        root.rename("a/missing", "x").err(),
+        Some(VfsError::NotFound)
+    );
+
+    // replacing an existing target
+    root.rename("f2", "a/b/h1").unwrap();
+    assert!(Arc::ptr_eq(&root.clone().lookup("a/b/h1").unwrap(), &f2));
+    assert_eq!(f1.get_attr().unwrap().nlink(), 0);
+    assert_eq!(
+        root.rename("a/b/h1", "d").err(),
+        Some(VfsError::IsADirectory)
+    );
+    assert_eq!(
+        root.rename("d", "a/b/h1").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.rename("a", "d").err(),
+        Some(VfsError::DirectoryNotEmpty)
+    );
+    assert_eq!(
+        root.rename("a/b", "a").err(),
+        Some(VfsError::DirectoryNotEmpty)
+    );
+
+    // a directory cannot be moved into its own subtree
+    assert_eq!(
+        root.rename("a", "a/b/a").err(),
+        Some(VfsError::InvalidInput)
+    );
+    assert_eq!(root.rename("a", "a/x").err(), Some(VfsError::InvalidInput));
+    assert_eq!(root.rename(".", "x").err(), Some(VfsError::InvalidInput));
+
+    // moved directories get the new parent
+    root.rename("a/b", "d/b").unwrap();
+    let b = root.clone().lookup("d/b").unwrap();
+    assert!(Arc::ptr_eq(
+        &b.clone().lookup("../f3").unwrap(),
+        &root.clone().lookup("d/f3").unwrap()
+    ));
+    assert_eq!(root.get_attr().unwrap().nlink(), 4);
+    assert_eq!(
+        root.clone()
+            .lookup("d")
+            .unwrap()
+            .get_attr()
+            .unwrap()
+            .nlink(),
+        3
+    );
+    root.rename("d/b", "a").unwrap();
+    assert!(Arc::ptr_eq(&root.clone().lookup("a").unwrap(), &b));
+    assert!(Arc::ptr_eq(&b.parent().unwrap(), &root));
+
+    // flags
+    let flags = RenameFlags::NOREPLACE;
+    assert_eq!(
+        root.rename_with_flags("a/h1", "d/f3", flags).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    root.rename_with_flags("a/h1", "d/f4", flags).unwrap();
+    let flags = RenameFlags::EXCHANGE;
+    assert_eq!(
+        root.rename_with_flags("d/f4", "a/h1", flags).err(),
+        Some(VfsError::NotFound)
+    );
+    root.rename_with_flags("d/f4", "a", flags).unwrap();
+    assert!(Arc::ptr_eq(&root.clone().lookup("a").unwrap(), &f2));
+    assert!(Arc::ptr_eq(&root.clone().lookup("d/f4").unwrap(), &b));
+    assert!(Arc::ptr_eq(
+        &b.parent().unwrap(),
+        &root.clone().lookup("d").unwrap()
+    ));
+    assert_eq!(
+        root.rename_with_flags("d", "d/f4/x", flags).err(),
+        Some(VfsError::NotFound)
+    );
+    assert_eq!(
+        root.rename_with_flags("d/f4", "d", flags).err(),
+        Some(VfsError::InvalidInput)
+    );
+    assert_eq!(
+        root.rename_with_flags("a", "d", RenameFlags::all()).err(),
+        Some(VfsError::InvalidInput)
+    );
+}
+
+#[test]
+fn test_dir_ops() {
+    use axfs_vfs::{RenameFlags, VfsDirOps, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef};
+
+    // a directory of another filesystem, which does not support `as_any()`
+    struct ForeignDir(Option<VfsNodeRef>);
+
+    impl VfsNodeOps for ForeignDir {
+        fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+            Ok(VfsNodeAttr::new_dir(0, 0))
+        }
+
+        fn parent(&self) -> Option<VfsNodeRef> {
+            self.0.clone()
+        }
+    }
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    let dir = ramfs.root_dir_node();
+    let perm = VfsNodePerm::default_file();
+
+    // single names
+    dir.create_entry("f1", VfsNodeType::File).unwrap();
+    dir.create_entry("d", VfsNodeType::Dir).unwrap();
+    let f1 = dir.lookup_entry("f1").unwrap();
+    assert_eq!(
+        dir.create_entry("f1", VfsNodeType::File).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    dir.mknod_entry("p", VfsNodeType::Fifo, perm, 0).unwrap();
+    dir.symlink_entry("l", "f1").unwrap();
+    dir.link_entry("f2", &f1).unwrap();
+    assert!(Arc::ptr_eq(&dir.lookup_entry("f2").unwrap(), &f1));
+    assert!(dir
+        .lookup_entry("l")
+        .unwrap()
+        .get_attr()
+        .unwrap()
+        .is_symlink());
+    let d = dir.lookup_entry("d").unwrap();
+    dir.rename_entry("f2", &d, "f3", RenameFlags::empty())
+        .unwrap();
+    assert!(Arc::ptr_eq(&root.clone().lookup("d/f3").unwrap(), &f1));
+    dir.remove_entry("p").unwrap();
+    assert_eq!(dir.lookup_entry("p").err(), Some(VfsError::NotFound));
+
+    // paths are walked to the directory of the last component
+    root.create("/d//e/", VfsNodeType::Dir).unwrap();
+    assert_eq!(root.create("d/e/.", VfsNodeType::Dir), Ok(()));
+    assert_eq!(
+        root.create("f1/g", VfsNodeType::File).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.mknod("d/..", VfsNodeType::Fifo, perm, 0).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        root.symlink(&"x".repeat(256), "f1").err(),
+        Some(VfsError::NameTooLong)
+    );
+    assert_eq!(
+        root.link("l/../d/e/f4", &f1).err(),
+        Some(VfsError::NotADirectory)
+    );
+    root.link("d/e/f4", &f1).unwrap();
+    assert_eq!(root.remove("d/e/."), Err(VfsError::InvalidInput));
+    root.remove("d/e/f4").unwrap();
+    assert_eq!(f1.get_attr().unwrap().nlink(), 2);
+
+    // renaming across filesystems, even if the other one cannot be downcast
+    let foreign: VfsNodeRef = Arc::new(ForeignDir(None));
+    let other = RamFileSystem::new();
+    other
+        .mount("/mnt", Arc::new(ForeignDir(Some(foreign))))
+        .unwrap();
+    let other_root = other.root_dir();
+    other_root.create("g1", VfsNodeType::File).unwrap();
+    assert_eq!(
+        other_root.rename("../g1", "g2").err(),
+        Some(VfsError::CrossesDevices)
+    );
+    assert_eq!(
+        other_root.rename("g1", "../g1").err(),
+        Some(VfsError::CrossesDevices)
+    );
+    let other = RamFileSystem::new();
+    other
+        .mount("/mnt", root.clone().lookup("d").unwrap())
+        .unwrap();
+    let other_root = other.root_dir();
+    other_root.create("g1", VfsNodeType::File).unwrap();
+    assert_eq!(
+        other_root.rename("../f1", "f1").err(),
+        Some(VfsError::CrossesDevices)
+    );
+    assert_eq!(
+        other_root.rename("g1", "../g1").err(),
+        Some(VfsError::CrossesDevices)
+    );
+}
+
+#[test]
+fn test_mknod() {
+    use axfs_vfs::{major, makedev, minor, VfsDirEntry, VfsNodePerm};
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("dev", VfsNodeType::Dir).unwrap();
+    let perm = VfsNodePerm::from_bits_truncate(0o620);
+    root.mknod("dev/console", VfsNodeType::CharDevice, perm, makedev(5, 1))
+        .unwrap();
+    root.mknod("dev/sda", VfsNodeType::BlockDevice, perm, makedev(8, 0))
+        .unwrap();
+    root.mknod("dev/../fifo", VfsNodeType::Fifo, perm, makedev(1, 1))
+        .unwrap();
+    root.create("sock", VfsNodeType::Socket).unwrap();
+    root.mknod("f1", VfsNodeType::File, perm, 0).unwrap();
+
+    let console = root.clone().lookup("dev/console").unwrap();
+    let attr = console.get_attr().unwrap();
+    assert_eq!(attr.file_type(), VfsNodeType::CharDevice);
+    assert_eq!((major(attr.rdev()), minor(attr.rdev())), (5, 1));
+    assert_eq!(attr.size(), 0);
+    assert_eq!(attr.perm().mode(), 0o620);
+    let special = console.as_any().downcast_ref::<SpecialNode>().unwrap();
+    assert_eq!(special.rdev(), makedev(5, 1));
+    let attr = root.clone().lookup("dev/sda").unwrap().get_attr().unwrap();
+    assert_eq!(
+        (attr.file_type(), attr.rdev()),
+        (VfsNodeType::BlockDevice, 0x800)
+    );
+    let attr = root.clone().lookup("fifo").unwrap().get_attr().unwrap();
+    assert_eq!((attr.file_type(), attr.rdev()), (VfsNodeType::Fifo, 0));
+    let attr = root.clone().lookup("sock").unwrap().get_attr().unwrap();
+    assert_eq!(attr.file_type(), VfsNodeType::Socket);
+    assert_eq!(attr.perm().mode(), 0o666);
+    let attr = root.clone().lookup("f1").unwrap().get_attr().unwrap();
+    assert!(attr.is_file());
+    assert_eq!(attr.perm().mode(), 0o620);
+    let info = ramfs.statfs().unwrap();
+    assert_eq!(info.files - info.files_free, 7);
+
+    assert_eq!(
+        root.mknod("dev/console", VfsNodeType::CharDevice, perm, 0)
+            .err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        root.mknod("l1", VfsNodeType::SymLink, perm, 0).err(),
+        Some(VfsError::Unsupported)
+    );
+    assert_eq!(
+        console.mknod("x", VfsNodeType::Fifo, perm, 0).err(),
+        Some(VfsError::NotADirectory)
+    );
+
+    // special files can be hard linked, renamed and listed
+    root.link("tty", &console).unwrap();
+    assert_eq!(console.get_attr().unwrap().nlink(), 2);
+    root.rename("dev/console", "dev/console1").unwrap();
+    let mut dirents: [VfsDirEntry; 4] = core::array::from_fn(|_| VfsDirEntry::default());
+    let dev = root.clone().lookup("dev").unwrap();
+    assert_eq!(dev.read_dir(0, &mut dirents), Ok(4));
+    assert_eq!(dirents[3].name_as_bytes(), b"console1");
+    assert_eq!(dirents[3].entry_type(), VfsNodeType::CharDevice);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_async() {
+    use axfs_vfs::{to_async_node, to_sync_node, AsyncNodeAdapter, AsyncVfsOps};
+    use axfs_vfs::{BlockingFsAdapter, BlockingNodeAdapter, VfsDirEntry, VfsNodeRef};
+    use axfs_vfs::{RenameFlags, XattrFlags};
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    /// Runs `future`, which never waits in the RAM filesystem.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(future).poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the future is pending"),
+        }
+    }
+
+    let ramfs = Arc::new(RamFileSystem::new());
+    let root = AsyncVfsOps::root_dir(&*ramfs);
+    block_on(async {
+        root.create("foo", VfsNodeType::Dir).await.unwrap();
+        root.create("foo/f1", VfsNodeType::File).await.unwrap();
+        let f1 = root.clone().lookup("foo/f1").await.unwrap();
+        assert_eq!(f1.write_at(0, b"hello").await, Ok(5));
+        let mut buf = [0; 8];
+        assert_eq!(f1.read_at(1, &mut buf).await, Ok(4));
+        assert_eq!(&buf[..4], b"ello");
+        assert_eq!(f1.truncate(2).await, Ok(()));
+        assert_eq!(f1.get_attr().await.unwrap().size(), 2);
+
+        let foo = root.clone().lookup("foo").await.unwrap();
+        let mut dirents: [VfsDirEntry; 4] = core::array::from_fn(|_| VfsDirEntry::default());
+        assert_eq!(foo.read_dir(0, &mut dirents).await, Ok(3));
+        assert_eq!(dirents[2].name_as_bytes(), b"f1");
+        assert_eq!(root.rename("foo/f1", "f2").await, Ok(()));
+        assert_eq!(root.remove("foo").await, Ok(()));
+        assert_eq!(
+            root.clone().lookup("foo").await.err(),
+            Some(VfsError::NotFound)
+        );
+        assert!(AsyncVfsOps::statfs(&*ramfs).await.is_ok());
+
+        // errors are the same as those of the blocking operations
+        let mut buf = [0; 8];
+        assert_eq!(root.read_at(0, &mut buf).await, Err(VfsError::IsADirectory));
+        assert_eq!(root.truncate(0).await, Err(VfsError::IsADirectory));
+        root.create("f3", VfsNodeType::File).await.unwrap();
+        let f3 = root.clone().lookup("f3").await.unwrap();
+        assert_eq!(
+            f3.clone().lookup("x").await.err(),
+            Some(VfsError::NotADirectory)
+        );
+        assert_eq!(f3.remove("x").await, Err(VfsError::NotADirectory));
+        assert_eq!(root.remove("f3").await, Ok(()));
+    });
+    let sync_root = VfsOps::root_dir(&*ramfs);
+    sync_root.symlink("l1", "f2").unwrap();
+    let l1 = sync_root.clone().lookup_nofollow("l1").unwrap();
+    let l1 = to_async_node(l1);
+    assert_eq!(
+        block_on(l1.clone().lookup("x")).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        block_on(l1.read_at(0, &mut [0; 4])).err(),
+        l1.clone()
+            .into_sync()
+            .unwrap()
+            .read_at(0, &mut [0; 4])
+            .err()
+    );
+    sync_root.remove("l1").unwrap();
+
+    // nodes in the RAM filesystem are converted without adapters
+    assert!(Arc::ptr_eq(&to_sync_node(root.clone()), &sync_root));
+    assert!(Arc::ptr_eq(
+        &to_sync_node(to_async_node(sync_root.clone())),
+        &sync_root
+    ));
+
+    // adapters work in both directions, and convert back to the inner nodes
+    let async_root = Arc::new(AsyncNodeAdapter::new(sync_root.clone()));
+    let adapted: VfsNodeRef = Arc::new(BlockingNodeAdapter::new(async_root.clone()));
+    assert!(Arc::ptr_eq(&to_sync_node(async_root), &sync_root));
+    let f2 = adapted.clone().lookup("f2").unwrap();
+    assert!(Arc::ptr_eq(&f2, &sync_root.clone().lookup("f2").unwrap()));
+    assert_eq!(adapted.create("f3", VfsNodeType::File), Ok(()));
+    assert!(sync_root.clone().lookup("f3").is_ok());
+
+    // the other operations are forwarded through the adapters too
+    let f3 = adapted.clone().lookup("f3").unwrap();
+    let f3: VfsNodeRef = Arc::new(BlockingNodeAdapter::new(Arc::new(AsyncNodeAdapter::new(
+        f3,
+    ))));
+    f3.set_xattr("user.a", b"1", XattrFlags::empty()).unwrap();
+    let mut buf = [0; 8];
+    assert_eq!(f3.get_xattr("user.a", &mut buf), Ok(1));
+    assert_eq!(f3.list_xattr(&mut buf), Ok(7));
+    assert!(f3.lock_manager().is_some());
+    assert_eq!(f3.ioctl(0, &mut []), Err(VfsError::NotATty));
+    assert_eq!(f3.write_at(0, &[1; 10]), Ok(10));
+    assert_eq!(f3.get_pages(0, 4096).unwrap().len(), 1);
+    assert_eq!(adapted.symlink("l2", "f3"), Ok(()));
+    let l2 = adapted.clone().lookup_nofollow("l2").unwrap();
+    assert_eq!(l2.readlink(&mut buf), Ok(2));
+    assert_eq!(adapted.link("f4", &f3), Ok(()));
+    assert_eq!(
+        adapted.rename_with_flags("f4", "l2", RenameFlags::NOREPLACE),
+        Err(VfsError::AlreadyExists)
+    );
+
+    let fs = BlockingFsAdapter::new(ramfs.clone());
+    assert!(Arc::ptr_eq(&fs.root_dir(), &sync_root));
+    assert_eq!(
+        fs.statfs().unwrap().files,
+        VfsOps::statfs(&*ramfs).unwrap().files
+    );
+}
@@ -0,0 +1,758 @@
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::future::{poll_fn, Future};
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
+
+use axerrno::ax_err;
+use spin::RwLock;
+
+use crate::{FallocateMode, FileSystemInfo, LockManager, PageRef, PollEvents, RenameFlags};
+use crate::{VfsAttrMask, VfsDirEntry, VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm};
+use crate::{VfsNodeRef, VfsNodeType, VfsOps, VfsResult, XattrFlags};
+
+/// A boxed future returned by the async node and filesystem operations.
+pub type VfsFuture<'a, T = ()> = Pin<Box<dyn Future<Output = VfsResult<T>> + Send + 'a>>;
+
+/// A wrapper of [`Arc<dyn AsyncVfsNodeOps>`].
+pub type AsyncVfsNodeRef = Arc<dyn AsyncVfsNodeOps>;
+
+/// A function that runs a future to completion.
+pub type BlockOnFn = fn(Pin<&mut dyn Future<Output = ()>>);
+
+static EXECUTOR: RwLock<Option<BlockOnFn>> = RwLock::new(None);
+
+/// Sets the executor used by the blocking adapters to wait for futures.
+///
+/// The function `block_on` should run the future to completion, putting the
+/// current task to sleep until the waker of the future is woken. It is usually
+/// provided by the kernel during initialization.
+///
+/// If no executor is set, the blocking adapters poll the futures only once,
+/// and return [`VfsError::WouldBlock`] if they are pending.
+pub fn set_executor(block_on: BlockOnFn) {
+    *EXECUTOR.write() = Some(block_on);
+}
+
+/// Async filesystem operations, the counterpart of [`VfsOps`].
+pub trait AsyncVfsOps: Send + Sync {
+    /// Do something when the filesystem is mounted.
+    fn mount<'a>(&'a self, _path: &'a str, _mount_point: AsyncVfsNodeRef) -> VfsFuture<'a> {
+        ready(Ok(()))
+    }
+
+    /// Do something when the filesystem is unmounted.
+    fn umount(&self) -> VfsFuture<'_> {
+        ready(Ok(()))
+    }
+
+    /// Format the filesystem.
+    fn format(&self) -> VfsFuture<'_> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Get the attributes of the filesystem.
+    fn statfs(&self) -> VfsFuture<'_, FileSystemInfo> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Get the root directory of the filesystem.
+    fn root_dir(&self) -> AsyncVfsNodeRef;
+
+    /// Get the device ID of the filesystem.
+    fn dev_id(&self) -> u64 {
+        0
+    }
+}
+
+/// Async node operations, the counterpart of [`VfsNodeOps`].
+///
+/// The operations that may wait for I/O return futures, the others are the
+/// same as those of [`VfsNodeOps`]. Operations with default implementations
+/// built on others in [`VfsNodeOps`], such as
+/// [`append()`](VfsNodeOps::append), are left out and available from the
+/// blocking node returned by [`to_sync_node()`].
+pub trait AsyncVfsNodeOps: Send + Sync {
+    /// Do something when the node is opened.
+    fn open(&self) -> VfsFuture<'_> {
+        ready(Ok(()))
+    }
+
+    /// Do something when the node is closed.
+    fn release(&self) -> VfsFuture<'_> {
+        ready(Ok(()))
+    }
+
+    /// Get the attributes of the node.
+    fn get_attr(&self) -> VfsFuture<'_, VfsNodeAttr> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Set the attributes of the node selected by `mask`, as
+    /// [`VfsNodeOps::set_attr`] does.
+    fn set_attr<'a>(&'a self, _attr: &'a VfsNodeAttr, _mask: VfsAttrMask) -> VfsFuture<'a> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Get the value of the extended attribute `name` into `buf`, as
+    /// [`VfsNodeOps::get_xattr`] does.
+    fn get_xattr<'a>(&'a self, _name: &'a str, _buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(ax_err!(OperationNotSupported))
+    }
+
+    /// Set the value of the extended attribute `name`, as
+    /// [`VfsNodeOps::set_xattr`] does.
+    fn set_xattr<'a>(
+        &'a self,
+        _name: &'a str,
+        _value: &'a [u8],
+        _flags: XattrFlags,
+    ) -> VfsFuture<'a> {
+        ready(ax_err!(OperationNotSupported))
+    }
+
+    /// List the names of all extended attributes into `buf`, as
+    /// [`VfsNodeOps::list_xattr`] does.
+    fn list_xattr<'a>(&'a self, _buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(ax_err!(OperationNotSupported))
+    }
+
+    /// Remove the extended attribute `name`.
+    fn remove_xattr<'a>(&'a self, _name: &'a str) -> VfsFuture<'a> {
+        ready(ax_err!(OperationNotSupported))
+    }
+
+    /// Get the manager of `flock` and POSIX advisory locks on the node.
+    fn lock_manager(&self) -> Option<&LockManager> {
+        None
+    }
+
+    /// Read data from the file at the given offset.
+    ///
+    /// Unlike [`VfsNodeOps::read_at`], the future waits until the node is
+    /// ready instead of returning [`VfsError::WouldBlock`].
+    fn read_at<'a>(&'a self, _offset: u64, _buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(ax_err!(InvalidInput))
+    }
+
+    /// Write data to the file at the given offset.
+    ///
+    /// Unlike [`VfsNodeOps::write_at`], the future waits until the node is
+    /// ready instead of returning [`VfsError::WouldBlock`].
+    fn write_at<'a>(&'a self, _offset: u64, _buf: &'a [u8]) -> VfsFuture<'a, usize> {
+        ready(ax_err!(InvalidInput))
+    }
+
+    /// Flush the file, synchronize the data to disk.
+    fn fsync(&self) -> VfsFuture<'_> {
+        ready(ax_err!(InvalidInput))
+    }
+
+    /// Truncate the file to the given size.
+    fn truncate(&self, _size: u64) -> VfsFuture<'_> {
+        ready(ax_err!(InvalidInput))
+    }
+
+    /// Manipulate the space of the file range `[offset, offset + len)`, as
+    /// [`VfsNodeOps::fallocate`] does.
+    fn fallocate(&self, _mode: FallocateMode, _offset: u64, _len: u64) -> VfsFuture<'_> {
+        ready(ax_err!(OperationNotSupported))
+    }
+
+    /// Perform the device-specific command `cmd`, as [`VfsNodeOps::ioctl`]
+    /// does.
+    fn ioctl<'a>(&'a self, _cmd: u32, _arg: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(ax_err!(NotATty))
+    }
+
+    /// Poll the I/O readiness of the file, as [`VfsNodeOps::poll`] does.
+    fn poll(&self, events: PollEvents, _waker: Option<&Waker>) -> VfsResult<PollEvents> {
+        Ok(events & (PollEvents::IN | PollEvents::OUT))
+    }
+
+    /// Get the pages backing the file range `[offset, offset + len)`, as
+    /// [`VfsNodeOps::get_pages`] does.
+    fn get_pages(&self, _offset: u64, _len: u64) -> VfsFuture<'_, Vec<PageRef>> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Write back the dirty pages in the file range `[offset, offset + len)`.
+    fn writeback_pages(&self, _offset: u64, _len: u64) -> VfsFuture<'_> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Read the target path of the symbolic link into `buf`.
+    fn readlink<'a>(&'a self, _buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(ax_err!(InvalidInput))
+    }
+
+    /// Get the parent directory of this directory.
+    fn parent(&self) -> Option<AsyncVfsNodeRef> {
+        None
+    }
+
+    /// Lookup the node with given `path` in the directory, as
+    /// [`VfsNodeOps::lookup`] does.
+    fn lookup<'a>(self: Arc<Self>, _path: &'a str) -> VfsFuture<'a, AsyncVfsNodeRef> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Lookup the node with given `path` in the directory, without following
+    /// the symbolic link at the last component.
+    ///
+    /// The default implementation calls [`lookup()`](Self::lookup).
+    fn lookup_nofollow<'a>(self: Arc<Self>, path: &'a str) -> VfsFuture<'a, AsyncVfsNodeRef> {
+        self.lookup(path)
+    }
+
+    /// Create a new node with the given `path` in the directory.
+    fn create<'a>(&'a self, _path: &'a str, _ty: VfsNodeType) -> VfsFuture<'a> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Create a special file with the given `path` in the directory, as
+    /// [`VfsNodeOps::mknod`] does.
+    fn mknod<'a>(
+        &'a self,
+        _path: &'a str,
+        _ty: VfsNodeType,
+        _perm: VfsNodePerm,
+        _rdev: u64,
+    ) -> VfsFuture<'a> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Create a symbolic link with the given `path` in the directory, which
+    /// points to `target`.
+    fn symlink<'a>(&'a self, _path: &'a str, _target: &'a str) -> VfsFuture<'a> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Create a hard link with the given `path` in the directory, which refers
+    /// to the existing `node`.
+    fn link<'a>(&'a self, _path: &'a str, _node: &'a AsyncVfsNodeRef) -> VfsFuture<'a> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Remove the node with the given `path` in the directory.
+    fn remove<'a>(&'a self, _path: &'a str) -> VfsFuture<'a> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Read directory entries into `dirents`, starting from the position
+    /// `cookie`, as [`VfsNodeOps::read_dir`] does.
+    fn read_dir<'a>(
+        &'a self,
+        _cookie: u64,
+        _dirents: &'a mut [VfsDirEntry],
+    ) -> VfsFuture<'a, usize> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Renames or moves existing file or directory.
+    fn rename<'a>(&'a self, _src_path: &'a str, _dst_path: &'a str) -> VfsFuture<'a> {
+        ready(ax_err!(Unsupported))
+    }
+
+    /// Renames or moves existing file or directory with `flags`, as
+    /// [`VfsNodeOps::rename_with_flags`] does.
+    ///
+    /// The default implementation only supports empty `flags`, and calls
+    /// [`rename()`](Self::rename).
+    fn rename_with_flags<'a>(
+        &'a self,
+        src_path: &'a str,
+        dst_path: &'a str,
+        flags: RenameFlags,
+    ) -> VfsFuture<'a> {
+        if !flags.is_empty() {
+            return ready(ax_err!(InvalidInput));
+        }
+        self.rename(src_path, dst_path)
+    }
+
+    /// Get the blocking operations of the node, if it also implements
+    /// [`VfsNodeOps`].
+    ///
+    /// Return `None` by default, then [`to_sync_node()`] wraps the node in a
+    /// [`BlockingNodeAdapter`].
+    fn into_sync(self: Arc<Self>) -> Option<VfsNodeRef> {
+        None
+    }
+}
+
+/// Converts a node to its async operations, which are native if it
+/// implements [`AsyncVfsNodeOps`], otherwise provided by an
+/// [`AsyncNodeAdapter`].
+pub fn to_async_node(node: VfsNodeRef) -> AsyncVfsNodeRef {
+    match node.clone().into_async() {
+        Some(node) => node,
+        None => Arc::new(AsyncNodeAdapter(node)),
+    }
+}
+
+/// Converts a node to its blocking operations, which are native if it
+/// implements [`VfsNodeOps`], otherwise provided by a
+/// [`BlockingNodeAdapter`].
+pub fn to_sync_node(node: AsyncVfsNodeRef) -> VfsNodeRef {
+    match node.clone().into_sync() {
+        Some(node) => node,
+        None => Arc::new(BlockingNodeAdapter(node)),
+    }
+}
+
+/// Runs the non-blocking I/O operation `op` on `node`, and waits for any of
+/// `events` with [`VfsNodeOps::poll`] while it returns
+/// [`VfsError::WouldBlock`].
+///
+/// Once the node reports [`PollEvents::ERR`] or [`PollEvents::HUP`], `op` is
+/// run one more time, and if it still would block, [`VfsError::Io`] or
+/// [`VfsError::BrokenPipe`] is returned instead of waiting. If the node
+/// reports ready but `op` still would block, the future wakes itself and
+/// yields to the executor instead of retrying at once.
+pub async fn poll_io<N, T, F>(node: &N, events: PollEvents, mut op: F) -> VfsResult<T>
+where
+    N: VfsNodeOps + ?Sized,
+    F: FnMut() -> VfsResult<T>,
+{
+    let mut hangup = None;
+    poll_fn(|cx| {
+        let mut retried = false;
+        loop {
+            match op() {
+                Err(VfsError::WouldBlock) => {}
+                res => return Poll::Ready(res),
+            }
+            if let Some(err) = hangup {
+                return Poll::Ready(Err(err));
+            }
+            if retried {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            // retry once if the node became ready before the waker was
+            // registered
+            match node.poll(events, Some(cx.waker())) {
+                Ok(ready) if ready.is_empty() => return Poll::Pending,
+                Ok(ready) if ready.contains(PollEvents::ERR) => hangup = Some(VfsError::Io),
+                Ok(ready) if ready.contains(PollEvents::HUP) => hangup = Some(VfsError::BrokenPipe),
+                Ok(_) => retried = true,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    })
+    .await
+}
+
+/// Waits for `future` with the executor set by [`set_executor()`], or polls
+/// it once if there is none.
+fn block_on<T>(mut future: VfsFuture<'_, T>) -> VfsResult<T> {
+    let Some(run) = *EXECUTOR.read() else {
+        let mut cx = Context::from_waker(Waker::noop());
+        return match future.as_mut().poll(&mut cx) {
+            Poll::Ready(res) => res,
+            Poll::Pending => ax_err!(WouldBlock),
+        };
+    };
+    let mut output = None;
+    run(pin!(async {
+        output = Some(future.await);
+    }));
+    output.unwrap_or(ax_err!(WouldBlock))
+}
+
+/// Provides [`AsyncVfsNodeOps`] for a node that only implements
+/// [`VfsNodeOps`].
+///
+/// Reading and writing wait for the node to be ready with [`poll_io()`],
+/// other operations complete immediately.
+pub struct AsyncNodeAdapter(VfsNodeRef);
+
+impl AsyncNodeAdapter {
+    /// Wraps `node`.
+    pub fn new(node: VfsNodeRef) -> Self {
+        Self(node)
+    }
+
+    /// Returns the wrapped node.
+    pub fn inner(&self) -> &VfsNodeRef {
+        &self.0
+    }
+}
+
+impl AsyncVfsNodeOps for AsyncNodeAdapter {
+    fn open(&self) -> VfsFuture<'_> {
+        ready(self.0.open())
+    }
+
+    fn release(&self) -> VfsFuture<'_> {
+        ready(self.0.release())
+    }
+
+    fn get_attr(&self) -> VfsFuture<'_, VfsNodeAttr> {
+        ready(self.0.get_attr())
+    }
+
+    fn set_attr<'a>(&'a self, attr: &'a VfsNodeAttr, mask: VfsAttrMask) -> VfsFuture<'a> {
+        ready(self.0.set_attr(attr, mask))
+    }
+
+    fn get_xattr<'a>(&'a self, name: &'a str, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(self.0.get_xattr(name, buf))
+    }
+
+    fn set_xattr<'a>(&'a self, name: &'a str, value: &'a [u8], flags: XattrFlags) -> VfsFuture<'a> {
+        ready(self.0.set_xattr(name, value, flags))
+    }
+
+    fn list_xattr<'a>(&'a self, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(self.0.list_xattr(buf))
+    }
+
+    fn remove_xattr<'a>(&'a self, name: &'a str) -> VfsFuture<'a> {
+        ready(self.0.remove_xattr(name))
+    }
+
+    fn lock_manager(&self) -> Option<&LockManager> {
+        self.0.lock_manager()
+    }
+
+    fn read_at<'a>(&'a self, offset: u64, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        Box::pin(poll_io(&*self.0, PollEvents::IN, move || {
+            self.0.read_at(offset, buf)
+        }))
+    }
+
+    fn write_at<'a>(&'a self, offset: u64, buf: &'a [u8]) -> VfsFuture<'a, usize> {
+        Box::pin(poll_io(&*self.0, PollEvents::OUT, move || {
+            self.0.write_at(offset, buf)
+        }))
+    }
+
+    fn fsync(&self) -> VfsFuture<'_> {
+        ready(self.0.fsync())
+    }
+
+    fn truncate(&self, size: u64) -> VfsFuture<'_> {
+        ready(self.0.truncate(size))
+    }
+
+    fn fallocate(&self, mode: FallocateMode, offset: u64, len: u64) -> VfsFuture<'_> {
+        ready(self.0.fallocate(mode, offset, len))
+    }
+
+    fn ioctl<'a>(&'a self, cmd: u32, arg: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(self.0.ioctl(cmd, arg))
+    }
+
+    fn poll(&self, events: PollEvents, waker: Option<&Waker>) -> VfsResult<PollEvents> {
+        self.0.poll(events, waker)
+    }
+
+    fn get_pages(&self, offset: u64, len: u64) -> VfsFuture<'_, Vec<PageRef>> {
+        ready(self.0.get_pages(offset, len))
+    }
+
+    fn writeback_pages(&self, offset: u64, len: u64) -> VfsFuture<'_> {
+        ready(self.0.writeback_pages(offset, len))
+    }
+
+    fn readlink<'a>(&'a self, buf: &'a mut [u8]) -> VfsFuture<'a, usize> {
+        ready(self.0.readlink(buf))
+    }
+
+    fn parent(&self) -> Option<AsyncVfsNodeRef> {
+        self.0.parent().map(to_async_node)
+    }
+
+    fn lookup<'a>(self: Arc<Self>, path: &'a str) -> VfsFuture<'a, AsyncVfsNodeRef> {
+        ready(self.0.clone().lookup(path).map(to_async_node))
+    }
+
+    fn lookup_nofollow<'a>(self: Arc<Self>, path: &'a str) -> VfsFuture<'a, AsyncVfsNodeRef> {
+        ready(self.0.clone().lookup_nofollow(path).map(to_async_node))
+    }
+
+    fn create<'a>(&'a self, path: &'a str, ty: VfsNodeType) -> VfsFuture<'a> {
+        ready(self.0.create(path, ty))
+    }
+
+    fn mknod<'a>(
+        &'a self,
+        path: &'a str,
+        ty: VfsNodeType,
+        perm: VfsNodePerm,
+        rdev: u64,
+    ) -> VfsFuture<'a> {
+        ready(self.0.mknod(path, ty, perm, rdev))
+    }
+
+    fn symlink<'a>(&'a self, path: &'a str, target: &'a str) -> VfsFuture<'a> {
+        ready(self.0.symlink(path, target))
+    }
+
+    fn link<'a>(&'a self, path: &'a str, node: &'a AsyncVfsNodeRef) -> VfsFuture<'a> {
+        ready(self.0.link(path, &to_sync_node(node.clone())))
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a> {
+        ready(self.0.remove(path))
+    }
+
+    fn read_dir<'a>(&'a self, cookie: u64, dirents: &'a mut [VfsDirEntry]) -> VfsFuture<'a, usize> {
+        ready(self.0.read_dir(cookie, dirents))
+    }
+
+    fn rename<'a>(&'a self, src_path: &'a str, dst_path: &'a str) -> VfsFuture<'a> {
+        ready(self.0.rename(src_path, dst_path))
+    }
+
+    fn rename_with_flags<'a>(
+        &'a self,
+        src_path: &'a str,
+        dst_path: &'a str,
+        flags: RenameFlags,
+    ) -> VfsFuture<'a> {
+        ready(self.0.rename_with_flags(src_path, dst_path, flags))
+    }
+
+    fn into_sync(self: Arc<Self>) -> Option<VfsNodeRef> {
+        Some(self.0.clone())
+    }
+}
+
+/// Provides [`VfsNodeOps`] for a node that only implements
+/// [`AsyncVfsNodeOps`], by waiting for the futures with the executor set by
+/// [`set_executor()`].
+pub struct BlockingNodeAdapter(AsyncVfsNodeRef);
+
+impl BlockingNodeAdapter {
+    /// Wraps `node`.
+    pub fn new(node: AsyncVfsNodeRef) -> Self {
+        Self(node)
+    }
+
+    /// Returns the wrapped node.
+    pub fn inner(&self) -> &AsyncVfsNodeRef {
+        &self.0
+    }
+}
+
+impl VfsNodeOps for BlockingNodeAdapter {
+    fn open(&self) -> VfsResult {
+        block_on(self.0.open())
+    }
+
+    fn release(&self) -> VfsResult {
+        block_on(self.0.release())
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        block_on(self.0.get_attr())
+    }
+
+    fn set_attr(&self, attr: &VfsNodeAttr, mask: VfsAttrMask) -> VfsResult {
+        block_on(self.0.set_attr(attr, mask))
+    }
+
+    fn get_xattr(&self, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        block_on(self.0.get_xattr(name, buf))
+    }
+
+    fn set_xattr(&self, name: &str, value: &[u8], flags: XattrFlags) -> VfsResult {
+        block_on(self.0.set_xattr(name, value, flags))
+    }
+
+    fn list_xattr(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        block_on(self.0.list_xattr(buf))
+    }
+
+    fn remove_xattr(&self, name: &str) -> VfsResult {
+        block_on(self.0.remove_xattr(name))
+    }
+
+    fn lock_manager(&self) -> Option<&LockManager> {
+        self.0.lock_manager()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        block_on(self.0.read_at(offset, buf))
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        block_on(self.0.write_at(offset, buf))
+    }
+
+    fn fsync(&self) -> VfsResult {
+        block_on(self.0.fsync())
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        block_on(self.0.truncate(size))
+    }
+
+    fn fallocate(&self, mode: FallocateMode, offset: u64, len: u64) -> VfsResult {
+        block_on(self.0.fallocate(mode, offset, len))
+    }
+
+    fn ioctl(&self, cmd: u32, arg: &mut [u8]) -> VfsResult<usize> {
+        block_on(self.0.ioctl(cmd, arg))
+    }
+
+    fn poll(&self, events: PollEvents, waker: Option<&Waker>) -> VfsResult<PollEvents> {
+        self.0.poll(events, waker)
+    }
+
+    fn get_pages(&self, offset: u64, len: u64) -> VfsResult<Vec<PageRef>> {
+        block_on(self.0.get_pages(offset, len))
+    }
+
+    fn writeback_pages(&self, offset: u64, len: u64) -> VfsResult {
+        block_on(self.0.writeback_pages(offset, len))
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        block_on(self.0.readlink(buf))
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.0.parent().map(to_sync_node)
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        block_on(self.0.clone().lookup(path)).map(to_sync_node)
+    }
+
+    fn lookup_nofollow(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        block_on(self.0.clone().lookup_nofollow(path)).map(to_sync_node)
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        block_on(self.0.create(path, ty))
+    }
+
+    fn mknod(&self, path: &str, ty: VfsNodeType, perm: VfsNodePerm, rdev: u64) -> VfsResult {
+        block_on(self.0.mknod(path, ty, perm, rdev))
+    }
+
+    fn symlink(&self, path: &str, target: &str) -> VfsResult {
+        block_on(self.0.symlink(path, target))
+    }
+
+    fn link(&self, path: &str, node: &VfsNodeRef) -> VfsResult {
+        let node = to_async_node(node.clone());
+        block_on(self.0.link(path, &node))
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        block_on(self.0.remove(path))
+    }
+
+    fn read_dir(&self, cookie: u64, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        block_on(self.0.read_dir(cookie, dirents))
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        block_on(self.0.rename(src_path, dst_path))
+    }
+
+    fn rename_with_flags(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
+        block_on(self.0.rename_with_flags(src_path, dst_path, flags))
+    }
+
+    fn into_async(self: Arc<Self>) -> Option<AsyncVfsNodeRef> {
+        Some(self.0.clone())
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+/// Provides [`AsyncVfsOps`] for a filesystem that only implements
+/// [`VfsOps`].
+pub struct AsyncFsAdapter(Arc<dyn VfsOps>);
+
+impl AsyncFsAdapter {
+    /// Wraps `fs`.
+    pub fn new(fs: Arc<dyn VfsOps>) -> Self {
+        Self(fs)
+    }
+
+    /// Returns the wrapped filesystem.
+    pub fn inner(&self) -> &Arc<dyn VfsOps> {
+        &self.0
+    }
+}
+
+impl AsyncVfsOps for AsyncFsAdapter {
+    fn mount<'a>(&'a self, path: &'a str, mount_point: AsyncVfsNodeRef) -> VfsFuture<'a> {
+        ready(self.0.mount(path, to_sync_node(mount_point)))
+    }
+
+    fn umount(&self) -> VfsFuture<'_> {
+        ready(self.0.umount())
+    }
+
+    fn format(&self) -> VfsFuture<'_> {
+        ready(self.0.format())
+    }
+
+    fn statfs(&self) -> VfsFuture<'_, FileSystemInfo> {
+        ready(self.0.statfs())
+    }
+
+    fn root_dir(&self) -> AsyncVfsNodeRef {
+        to_async_node(self.0.root_dir())
+    }
+
+    fn dev_id(&self) -> u64 {
+        self.0.dev_id()
+    }
+}
+
+/// Provides [`VfsOps`] for a filesystem that only implements
+/// [`AsyncVfsOps`], by waiting for the futures with the executor set by
+/// [`set_executor()`].
+pub struct BlockingFsAdapter(Arc<dyn AsyncVfsOps>);
+
+impl BlockingFsAdapter {
+    /// Wraps `fs`.
+    pub fn new(fs: Arc<dyn AsyncVfsOps>) -> Self {
+        Self(fs)
+    }
+
+    /// Returns the wrapped filesystem.
+    pub fn inner(&self) -> &Arc<dyn AsyncVfsOps> {
+        &self.0
+    }
+}
+
+impl VfsOps for BlockingFsAdapter {
+    fn mount(&self, path: &str, mount_point: VfsNodeRef) -> VfsResult {
+        block_on(self.0.mount(path, to_async_node(mount_point)))
+    }
+
+    fn umount(&self) -> VfsResult {
+        block_on(self.0.umount())
+    }
+
+    fn format(&self) -> VfsResult {
+        block_on(self.0.format())
+    }
+
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        block_on(self.0.statfs())
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        to_sync_node(self.0.root_dir())
+    }
+
+    fn dev_id(&self) -> u64 {
+        self.0.dev_id()
+    }
+}
+
+fn ready<'a, T: Send + 'a>(result: VfsResult<T>) -> VfsFuture<'a, T> {
+    Box::pin(core::future::ready(result))
+}
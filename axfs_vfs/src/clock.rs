@@ -0,0 +1,22 @@
+use core::time::Duration;
+
+use spin::RwLock;
+
+static CLOCK_SOURCE: RwLock<fn() -> Duration> = RwLock::new(|| Duration::ZERO);
+
+/// Sets the clock source used to timestamp nodes.
+///
+/// The function `now` should return the current wall-clock time, as the
+/// duration since the Unix epoch. It is usually provided by the kernel during
+/// initialization.
+pub fn set_clock_source(now: fn() -> Duration) {
+    *CLOCK_SOURCE.write() = now;
+}
+
+/// Returns the current time from the clock source, as the duration since the
+/// Unix epoch.
+///
+/// Returns [`Duration::ZERO`] if no clock source is set.
+pub fn current_time() -> Duration {
+    (CLOCK_SOURCE.read())()
+}
@@ -0,0 +1,50 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{VfsError, VfsNodeOps, VfsNodeRef, VfsResult, PAGE_SIZE};
+
+/// Copies `len` bytes from `src` at `src_offset` to `dst` at `offset` through
+/// a buffer, with [`read_at`](VfsNodeOps::read_at) and
+/// [`write_at`](VfsNodeOps::write_at).
+///
+/// This is the default implementation of
+/// [`VfsNodeOps::copy_range`], which filesystems can fall back to when the
+/// source is not one of their nodes. Returns [`VfsError::InvalidInput`] if
+/// either range overflows.
+pub fn generic_copy_range<N: VfsNodeOps + ?Sized>(
+    dst: &N,
+    offset: u64,
+    src: &VfsNodeRef,
+    src_offset: u64,
+    len: u64,
+) -> VfsResult<u64> {
+    if offset.checked_add(len).is_none() || src_offset.checked_add(len).is_none() {
+        return Err(VfsError::InvalidInput);
+    }
+    let mut buf = copy_buffer(len);
+    let mut total = 0;
+    while total < len {
+        let chunk = &mut buf[..(len - total).min(PAGE_SIZE as u64) as usize];
+        let ret = src
+            .read_at(src_offset + total, chunk)
+            .and_then(|n| dst.write_at(offset + total, &chunk[..n]).map(|m| (n, m)));
+        match ret {
+            Ok((n, m)) => {
+                total += m as u64;
+                if n < chunk.len() || m < n {
+                    break;
+                }
+            }
+            Err(_) if total > 0 => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+/// Allocates a zero-filled buffer for copying `len` bytes a page at a time,
+/// which is no larger than [`PAGE_SIZE`].
+///
+/// The buffer is on the heap, as kernel stacks are small.
+pub fn copy_buffer(len: u64) -> Vec<u8> {
+    vec![0; len.min(PAGE_SIZE as u64) as usize]
+}
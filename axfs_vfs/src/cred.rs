@@ -0,0 +1,114 @@
+use alloc::vec::Vec;
+
+use crate::{VfsError, VfsNodeAttr, VfsResult};
+
+bitflags::bitflags! {
+    /// Kinds of access to a node, same as `R_OK`, `W_OK` and `X_OK` of
+    /// `access` in Linux.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct AccessMode: u32 {
+        /// Read the file, or list the directory.
+        const READ = 4;
+        /// Write the file, or create and remove entries in the directory.
+        const WRITE = 2;
+        /// Execute the file, or search the directory.
+        const EXECUTE = 1;
+    }
+}
+
+/// User and group IDs used to check the access to nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    uid: u32,
+    gid: u32,
+    groups: Vec<u32>,
+}
+
+impl Credentials {
+    /// Creates credentials with the given user and group IDs, and no
+    /// supplementary groups.
+    pub const fn new(uid: u32, gid: u32) -> Self {
+        Self {
+            uid,
+            gid,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Creates credentials of the superuser.
+    pub const fn root() -> Self {
+        Self::new(0, 0)
+    }
+
+    /// Sets the supplementary group IDs.
+    pub fn with_groups(mut self, groups: &[u32]) -> Self {
+        self.groups = groups.into();
+        self
+    }
+
+    /// Returns the user ID.
+    pub const fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the group ID.
+    pub const fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Returns the supplementary group IDs.
+    pub fn groups(&self) -> &[u32] {
+        &self.groups
+    }
+
+    /// Whether the credentials are of the superuser.
+    pub const fn is_root(&self) -> bool {
+        self.uid == 0
+    }
+
+    /// Whether `gid` is the group ID or one of the supplementary group IDs.
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.gid == gid || self.groups.contains(&gid)
+    }
+
+    /// Checks whether the credentials allow the access `mode` to the node
+    /// with attributes `attr`.
+    ///
+    /// The permission bits of the owner, the group or others are used, in
+    /// this order, whichever the credentials first match. The superuser can
+    /// always read and write, and can execute directories and any file with
+    /// an execute bit set.
+    ///
+    /// Returns [`VfsError::PermissionDenied`] if the access is not allowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use axfs_vfs::{AccessMode, Credentials, VfsNodeAttr};
+    ///
+    /// let mut attr = VfsNodeAttr::new_file(0, 0); // rw-rw-rw-
+    /// attr.set_uid(1000);
+    /// let user = Credentials::new(1000, 1000);
+    /// assert!(user.check_access(&attr, AccessMode::WRITE).is_ok());
+    /// assert!(user.check_access(&attr, AccessMode::EXECUTE).is_err());
+    /// assert!(Credentials::root().check_access(&attr, AccessMode::EXECUTE).is_err());
+    /// ```
+    pub fn check_access(&self, attr: &VfsNodeAttr, mode: AccessMode) -> VfsResult {
+        let perm = attr.perm().bits() as u32;
+        let allowed = if self.is_root() {
+            let exec = attr.is_dir() || perm & 0o111 != 0;
+            (AccessMode::READ | AccessMode::WRITE).bits() | exec as u32
+        } else if self.uid == attr.uid() {
+            perm >> 6
+        } else if self.in_group(attr.gid()) {
+            perm >> 3
+        } else {
+            perm
+        };
+        if AccessMode::from_bits_truncate(allowed).contains(mode) {
+            Ok(())
+        } else {
+            Err(VfsError::PermissionDenied)
+        }
+    }
+}
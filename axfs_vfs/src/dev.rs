@@ -0,0 +1,41 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_ANON_DEV: AtomicU64 = AtomicU64::new(1);
+
+/// Allocates a new anonymous device ID for a filesystem that is not backed by
+/// a block device, such as a RAM filesystem.
+///
+/// The returned ID is unique among all calls.
+pub fn alloc_anon_dev() -> u64 {
+    NEXT_ANON_DEV.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Makes a device ID from the major and minor numbers, in the same encoding
+/// as `makedev` in Linux.
+///
+/// # Examples
+///
+/// ```
+/// use axfs_vfs::{major, makedev, minor};
+///
+/// let dev = makedev(5, 1); // /dev/console
+/// assert_eq!(dev, 0x501);
+/// assert_eq!((major(dev), minor(dev)), (5, 1));
+/// ```
+pub const fn makedev(major: u32, minor: u32) -> u64 {
+    let (major, minor) = (major as u64, minor as u64);
+    ((major & 0xffff_f000) << 32)
+        | ((major & 0xfff) << 8)
+        | ((minor & 0xffff_ff00) << 12)
+        | (minor & 0xff)
+}
+
+/// Returns the major number of the device ID `dev`.
+pub const fn major(dev: u64) -> u32 {
+    (((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff)) as u32
+}
+
+/// Returns the minor number of the device ID `dev`.
+pub const fn minor(dev: u64) -> u32 {
+    (((dev >> 12) & 0xffff_ff00) | (dev & 0xff)) as u32
+}
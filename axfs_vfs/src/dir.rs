@@ -0,0 +1,222 @@
+//! Directory operations on single names, and the path-taking operations of
+//! [`VfsNodeOps`] built on them.
+//!
+//! A filesystem implements [`VfsDirOps`] on its directory nodes, and adds
+//! [`impl_vfs_dir_paths!`](crate::impl_vfs_dir_paths) to their
+//! [`VfsNodeOps`] implementations, so it never parses paths itself. The
+//! functions in this module are what the macro expands to.
+//!
+//! Like other paths given to the node operations, paths are relative to the
+//! directory even if they start with a slash. They are walked with a
+//! [`PathResolver`], which also looks up names in other filesystems reached
+//! through `..` or symbolic links.
+
+use crate::{Credentials, PathResolver, RenameFlags, VfsError, VfsNodeOps, VfsNodeRef};
+use crate::{VfsNodePerm, VfsNodeType, VfsResult, NAME_MAX};
+use axerrno::ax_err;
+
+/// Operations on a single entry of a directory.
+///
+/// Names given to these operations are never empty, `.` or `..`, nor longer
+/// than [`NAME_MAX`].
+pub trait VfsDirOps: VfsNodeOps {
+    /// Get the [`VfsNodeRef`] of this directory.
+    fn node_ref(&self) -> VfsNodeRef;
+
+    /// Look up the entry `name`, without following it if it is a symbolic
+    /// link.
+    fn lookup_entry(&self, name: &str) -> VfsResult<VfsNodeRef>;
+
+    /// Create a new node `name` of type `ty`.
+    fn create_entry(&self, _name: &str, _ty: VfsNodeType) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a special file `name`, as [`VfsNodeOps::mknod`] does.
+    fn mknod_entry(
+        &self,
+        _name: &str,
+        _ty: VfsNodeType,
+        _perm: VfsNodePerm,
+        _rdev: u64,
+    ) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a symbolic link `name`, which points to `target`.
+    fn symlink_entry(&self, _name: &str, _target: &str) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a hard link `name`, which refers to the existing `node`.
+    fn link_entry(&self, _name: &str, _node: &VfsNodeRef) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Remove the entry `name`.
+    fn remove_entry(&self, _name: &str) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Rename the entry `src_name` to `dst_name` in `dst_dir`, as
+    /// [`VfsNodeOps::rename_with_flags`] does.
+    ///
+    /// `dst_dir` is a directory, but may be in another filesystem, in which
+    /// case [`VfsError::CrossesDevices`] should be returned. Check its device
+    /// before downcasting it, nodes of other filesystems may not support
+    /// [`as_any()`](VfsNodeOps::as_any).
+    fn rename_entry(
+        &self,
+        _src_name: &str,
+        _dst_dir: &VfsNodeRef,
+        _dst_name: &str,
+        _flags: RenameFlags,
+    ) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+}
+
+/// Looks up `path` in `dir`, as [`VfsNodeOps::lookup`],
+/// [`VfsNodeOps::lookup_nofollow`] and [`VfsNodeOps::lookup_as`] do.
+pub fn lookup<T: VfsDirOps + ?Sized>(
+    dir: &T,
+    path: &str,
+    follow: bool,
+    cred: Option<&Credentials>,
+) -> VfsResult<VfsNodeRef> {
+    let path = path.trim_start_matches('/');
+    // the resolver looks up every name this way, so do not walk again
+    if !follow && cred.is_none() && !path.contains('/') && !is_special(path) {
+        check_name(path)?;
+        return dir.lookup_entry(path);
+    }
+    let mut resolver = PathResolver::new();
+    if let Some(cred) = cred {
+        resolver = resolver.with_credentials(cred);
+    }
+    resolver.resolve(&dir.node_ref(), path, follow)
+}
+
+/// Creates a node at `path` in `dir`, as [`VfsNodeOps::create`] does.
+pub fn create<T: VfsDirOps + ?Sized>(dir: &T, path: &str, ty: VfsNodeType) -> VfsResult {
+    match walk_parent(dir, path)? {
+        (_, name) if is_special(name) => Ok(()), // already exists
+        (Some(parent), name) => parent.create(name, ty),
+        (None, name) => dir.create_entry(name, ty),
+    }
+}
+
+/// Creates a special file at `path` in `dir`, as [`VfsNodeOps::mknod`] does.
+pub fn mknod<T: VfsDirOps + ?Sized>(
+    dir: &T,
+    path: &str,
+    ty: VfsNodeType,
+    perm: VfsNodePerm,
+    rdev: u64,
+) -> VfsResult {
+    match walk_parent(dir, path)? {
+        (_, name) if is_special(name) => ax_err!(AlreadyExists),
+        (Some(parent), name) => parent.mknod(name, ty, perm, rdev),
+        (None, name) => dir.mknod_entry(name, ty, perm, rdev),
+    }
+}
+
+/// Creates a symbolic link at `path` in `dir`, as [`VfsNodeOps::symlink`]
+/// does.
+pub fn symlink<T: VfsDirOps + ?Sized>(dir: &T, path: &str, target: &str) -> VfsResult {
+    match walk_parent(dir, path)? {
+        (_, name) if is_special(name) => ax_err!(AlreadyExists),
+        (Some(parent), name) => parent.symlink(name, target),
+        (None, name) => dir.symlink_entry(name, target),
+    }
+}
+
+/// Creates a hard link at `path` in `dir`, as [`VfsNodeOps::link`] does.
+pub fn link<T: VfsDirOps + ?Sized>(dir: &T, path: &str, node: &VfsNodeRef) -> VfsResult {
+    match walk_parent(dir, path)? {
+        (_, name) if is_special(name) => ax_err!(AlreadyExists),
+        (Some(parent), name) => parent.link(name, node),
+        (None, name) => dir.link_entry(name, node),
+    }
+}
+
+/// Removes the node at `path` in `dir`, as [`VfsNodeOps::remove`] does.
+pub fn remove<T: VfsDirOps + ?Sized>(dir: &T, path: &str) -> VfsResult {
+    match walk_parent(dir, path)? {
+        (_, name) if is_special(name) => ax_err!(InvalidInput), // remove '.' or '..'
+        (Some(parent), name) => parent.remove(name),
+        (None, name) => dir.remove_entry(name),
+    }
+}
+
+/// Renames the node at `src_path` in `dir` to `dst_path`, as
+/// [`VfsNodeOps::rename_with_flags`] does.
+///
+/// The directory containing the source must be in the same filesystem as
+/// `dir`, which is told by the device of their attributes, otherwise
+/// [`VfsError::CrossesDevices`] is returned. Only then it is downcast to `T`,
+/// so nodes of other filesystems need not support
+/// [`as_any()`](VfsNodeOps::as_any).
+pub fn rename<T: VfsDirOps + 'static>(
+    dir: &T,
+    src_path: &str,
+    dst_path: &str,
+    flags: RenameFlags,
+) -> VfsResult {
+    let (src_dir, src_name) = walk_parent(dir, src_path)?;
+    let (dst_dir, dst_name) = walk_parent(dir, dst_path)?;
+    if is_special(src_name) || is_special(dst_name) {
+        return ax_err!(InvalidInput);
+    }
+    let dst_dir = dst_dir.unwrap_or_else(|| dir.node_ref());
+    if !dst_dir.get_attr()?.is_dir() {
+        return ax_err!(NotADirectory);
+    }
+    let Some(src_dir) = src_dir else {
+        return dir.rename_entry(src_name, &dst_dir, dst_name, flags);
+    };
+    let attr = src_dir.get_attr()?;
+    if !attr.is_dir() {
+        return ax_err!(NotADirectory);
+    }
+    if attr.dev() != dir.get_attr()?.dev() {
+        return ax_err!(CrossesDevices);
+    }
+    match src_dir.as_any().downcast_ref::<T>() {
+        Some(src_dir) => src_dir.rename_entry(src_name, &dst_dir, dst_name, flags),
+        None => ax_err!(CrossesDevices),
+    }
+}
+
+/// Finds the directory containing the last component of `path`, and returns
+/// it with the name of the last component.
+///
+/// The directory is `None` if it is `dir` itself, then the name is checked
+/// to be a valid entry name unless it is a special one.
+fn walk_parent<'a, T: VfsDirOps + ?Sized>(
+    dir: &T,
+    path: &'a str,
+) -> VfsResult<(Option<VfsNodeRef>, &'a str)> {
+    let path = path.trim_start_matches('/');
+    let name = path.trim_end_matches('/');
+    if name.contains('/') {
+        let (parent, name) = PathResolver::new().resolve_parent(&dir.node_ref(), path)?;
+        return Ok((Some(parent), name));
+    }
+    if !is_special(name) {
+        check_name(name)?;
+    }
+    Ok((None, name))
+}
+
+/// Whether `name` is empty, `.` or `..`, which do not name an entry.
+fn is_special(name: &str) -> bool {
+    matches!(name, "" | "." | "..")
+}
+
+fn check_name(name: &str) -> VfsResult {
+    if name.len() > NAME_MAX {
+        return Err(VfsError::NameTooLong);
+    }
+    Ok(())
+}
@@ -0,0 +1,304 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use spin::Mutex;
+
+use crate::{FallocateMode, VfsDirEntry, VfsError, VfsNodeAttr, VfsNodeRef, VfsResult};
+
+bitflags::bitflags! {
+    /// Flags used to open a file, same as the `O_*` constants in Linux.
+    ///
+    /// The file is opened read-only if neither [`WRONLY`](Self::WRONLY) nor
+    /// [`RDWR`](Self::RDWR) is set.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct OpenFlags: u32 {
+        /// Open for writing only.
+        const WRONLY = 0o1;
+        /// Open for reading and writing.
+        const RDWR = 0o2;
+        /// Create the file if it does not exist.
+        const CREAT = 0o100;
+        /// Fail if the file already exists, used with [`CREAT`](Self::CREAT).
+        const EXCL = 0o200;
+        /// Do not make the terminal the controlling terminal.
+        const NOCTTY = 0o400;
+        /// Truncate the file to zero length.
+        const TRUNC = 0o1000;
+        /// Write at the end of file.
+        const APPEND = 0o2000;
+        /// Return [`VfsError::WouldBlock`] instead of blocking.
+        const NONBLOCK = 0o4000;
+        /// Fail if the file is not a directory.
+        const DIRECTORY = 0o200000;
+        /// Do not follow the symbolic link at the last component.
+        const NOFOLLOW = 0o400000;
+        /// Close the file on `execve`.
+        const CLOEXEC = 0o2000000;
+    }
+}
+
+impl OpenFlags {
+    /// Mask of the access mode bits.
+    pub const ACCMODE: Self = Self::WRONLY.union(Self::RDWR);
+
+    /// Flags that can be changed after the file is opened, as `F_SETFL`
+    /// does.
+    pub const SETTABLE: Self = Self::APPEND.union(Self::NONBLOCK);
+
+    /// Returns `true` if the file is opened for reading.
+    pub const fn readable(&self) -> bool {
+        !self.contains(Self::WRONLY)
+    }
+
+    /// Returns `true` if the file is opened for writing.
+    pub const fn writable(&self) -> bool {
+        self.intersects(Self::ACCMODE)
+    }
+}
+
+/// Possible ways to move the offset of a [`VfsFile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    /// Set the offset to the given number of bytes.
+    Start(u64),
+    /// Set the offset to the current offset plus the given number of bytes.
+    Current(i64),
+    /// Set the offset to the size of the file plus the given number of bytes.
+    End(i64),
+    /// Set the offset to the next data region at or after the given offset,
+    /// as `SEEK_DATA` does.
+    Data(u64),
+    /// Set the offset to the next hole at or after the given offset, as
+    /// `SEEK_HOLE` does.
+    Hole(u64),
+}
+
+/// An opened file, i.e. an open file description in POSIX.
+///
+/// It holds the current offset and the flags of a node opened once, and may
+/// be shared by several file descriptors. The node is opened with
+/// [`VfsNodeOps::open`](crate::VfsNodeOps::open) when the file is created,
+/// and released with [`VfsNodeOps::release`](crate::VfsNodeOps::release)
+/// when the file is dropped.
+///
+/// Path lookup and node creation for [`OpenFlags::CREAT`],
+/// [`OpenFlags::EXCL`] and [`OpenFlags::NOFOLLOW`] are left to the caller.
+pub struct VfsFile {
+    node: VfsNodeRef,
+    flags: AtomicU32,
+    offset: Mutex<u64>,
+}
+
+impl VfsFile {
+    /// Opens `node` with the given flags.
+    ///
+    /// Returns [`VfsError::NotADirectory`] if [`OpenFlags::DIRECTORY`] is set
+    /// on a non-directory, and [`VfsError::IsADirectory`] if a directory is
+    /// opened for writing. The file is truncated if [`OpenFlags::TRUNC`] is
+    /// set and it is opened for writing.
+    pub fn open(node: VfsNodeRef, flags: OpenFlags) -> VfsResult<Self> {
+        let attr = node.get_attr()?;
+        if flags.contains(OpenFlags::DIRECTORY) && !attr.is_dir() {
+            return Err(VfsError::NotADirectory);
+        }
+        if attr.is_dir() && flags.writable() {
+            return Err(VfsError::IsADirectory);
+        }
+
+        node.open()?;
+        // from now on, the node is released when `file` is dropped
+        let file = Self {
+            node,
+            flags: AtomicU32::new(flags.bits()),
+            offset: Mutex::new(0),
+        };
+        if flags.contains(OpenFlags::TRUNC) && flags.writable() && attr.is_file() {
+            file.node.truncate(0)?;
+        }
+        Ok(file)
+    }
+
+    /// Returns the opened node.
+    pub fn node(&self) -> &VfsNodeRef {
+        &self.node
+    }
+
+    /// Returns the flags of the file.
+    pub fn flags(&self) -> OpenFlags {
+        OpenFlags::from_bits_retain(self.flags.load(Ordering::Acquire))
+    }
+
+    /// Changes the flags in [`OpenFlags::SETTABLE`] to those in `flags`, as
+    /// `F_SETFL` does. Other flags are ignored.
+    pub fn set_flags(&self, flags: OpenFlags) {
+        let _ = self
+            .flags
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |old| {
+                let old = OpenFlags::from_bits_retain(old);
+                let new = (old - OpenFlags::SETTABLE) | (flags & OpenFlags::SETTABLE);
+                Some(new.bits())
+            });
+    }
+
+    /// Returns `true` if [`OpenFlags::NONBLOCK`] is set.
+    ///
+    /// Nodes always return [`VfsError::WouldBlock`] when not ready, it is up
+    /// to the caller to wait for them if the file is blocking.
+    pub fn is_nonblocking(&self) -> bool {
+        self.flags().contains(OpenFlags::NONBLOCK)
+    }
+
+    /// Returns the current offset.
+    pub fn offset(&self) -> u64 {
+        *self.offset.lock()
+    }
+
+    /// Gets the attributes of the opened node.
+    pub fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        self.node.get_attr()
+    }
+
+    fn check_readable(&self) -> VfsResult {
+        if self.flags().readable() {
+            Ok(())
+        } else {
+            Err(VfsError::BadFileDescriptor)
+        }
+    }
+
+    fn check_writable(&self) -> VfsResult {
+        if self.flags().writable() {
+            Ok(())
+        } else {
+            Err(VfsError::BadFileDescriptor)
+        }
+    }
+
+    /// Reads data at the current offset into `buf`, and advances the offset.
+    pub fn read(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        self.check_readable()?;
+        let mut offset = self.offset.lock();
+        let len = self.node.read_at(*offset, buf)?;
+        *offset += len as u64;
+        Ok(len)
+    }
+
+    /// Writes `buf` at the current offset, and advances the offset.
+    ///
+    /// If [`OpenFlags::APPEND`] is set, data is written to the end of file
+    /// with [`VfsNodeOps::append`](crate::VfsNodeOps::append), and the offset
+    /// is moved after it.
+    pub fn write(&self, buf: &[u8]) -> VfsResult<usize> {
+        self.check_writable()?;
+        let mut offset = self.offset.lock();
+        if self.flags().contains(OpenFlags::APPEND) {
+            let (end, len) = self.node.append(&[buf])?;
+            *offset = end + len as u64;
+            return Ok(len);
+        }
+        let len = self.node.write_at(*offset, buf)?;
+        *offset += len as u64;
+        Ok(len)
+    }
+
+    /// Reads data at the current offset into `bufs` in order, and advances
+    /// the offset, as `readv` does.
+    pub fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> VfsResult<usize> {
+        self.check_readable()?;
+        let mut offset = self.offset.lock();
+        let len = self.node.read_vectored_at(*offset, bufs)?;
+        *offset += len as u64;
+        Ok(len)
+    }
+
+    /// Writes `bufs` in order at the current offset, and advances the offset,
+    /// as `writev` does.
+    ///
+    /// If [`OpenFlags::APPEND`] is set, data is written to the end of file
+    /// with [`VfsNodeOps::append`](crate::VfsNodeOps::append), and the offset
+    /// is moved after it.
+    pub fn write_vectored(&self, bufs: &[&[u8]]) -> VfsResult<usize> {
+        self.check_writable()?;
+        let mut offset = self.offset.lock();
+        if self.flags().contains(OpenFlags::APPEND) {
+            let (end, len) = self.node.append(bufs)?;
+            *offset = end + len as u64;
+            return Ok(len);
+        }
+        let len = self.node.write_vectored_at(*offset, bufs)?;
+        *offset += len as u64;
+        Ok(len)
+    }
+
+    /// Reads data at `offset` into `buf`, without using or changing the
+    /// current offset.
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.check_readable()?;
+        self.node.read_at(offset, buf)
+    }
+
+    /// Writes `buf` at `offset`, without using or changing the current
+    /// offset.
+    ///
+    /// As in Linux, data is still appended to the end of file if
+    /// [`OpenFlags::APPEND`] is set.
+    pub fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.check_writable()?;
+        if self.flags().contains(OpenFlags::APPEND) {
+            return Ok(self.node.append(&[buf])?.1);
+        }
+        self.node.write_at(offset, buf)
+    }
+
+    /// Moves the current offset, and returns the new offset.
+    ///
+    /// Returns [`VfsError::InvalidInput`] if the new offset would be
+    /// negative or overflow. For [`SeekFrom::Data`] and [`SeekFrom::Hole`],
+    /// returns [`VfsError::NoSuchDevice`] (`ENXIO` in Linux) if there is no
+    /// such region at or after the given offset.
+    pub fn seek(&self, pos: SeekFrom) -> VfsResult<u64> {
+        let mut offset = self.offset.lock();
+        let new = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(delta) => offset.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.node.get_attr()?.size().checked_add_signed(delta),
+            SeekFrom::Data(pos) => Some(self.node.seek_data(pos)?.ok_or(VfsError::NoSuchDevice)?),
+            SeekFrom::Hole(pos) => Some(self.node.seek_hole(pos)?.ok_or(VfsError::NoSuchDevice)?),
+        };
+        *offset = new.ok_or(VfsError::InvalidInput)?;
+        Ok(*offset)
+    }
+
+    /// Truncates the file to `size`.
+    pub fn truncate(&self, size: u64) -> VfsResult {
+        self.check_writable()?;
+        self.node.truncate(size)
+    }
+
+    /// Manipulates the space of the range `[offset, offset + len)` of the
+    /// file, as `fallocate` does.
+    pub fn fallocate(&self, mode: FallocateMode, offset: u64, len: u64) -> VfsResult {
+        self.check_writable()?;
+        self.node.fallocate(mode, offset, len)
+    }
+
+    /// Reads directory entries into `dirents`, starting from the current
+    /// offset, and moves the offset to that of the last entry read.
+    ///
+    /// The offset of a directory is an opaque cookie, which can only be
+    /// restored with [`SeekFrom::Start`] from a value previously returned by
+    /// [`offset()`](Self::offset) or [`VfsDirEntry::offset`].
+    pub fn read_dir(&self, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let mut offset = self.offset.lock();
+        let count = self.node.read_dir(*offset, dirents)?;
+        if let Some(last) = dirents[..count].last() {
+            *offset = last.offset();
+        }
+        Ok(count)
+    }
+}
+
+impl Drop for VfsFile {
+    fn drop(&mut self) {
+        self.node.release().ok();
+    }
+}
@@ -1,8 +1,8 @@
 //! Virtual filesystem interfaces used by [ArceOS](https://github.com/arceos-org/arceos).
 //!
-//! A filesystem is a set of files and directories (symbol links are not
-//! supported currently), collectively referred to as **nodes**, which are
-//! conceptually similar to [inodes] in Linux. A file system needs to implement
+//! A filesystem is a set of files, directories and symbolic links,
+//! collectively referred to as **nodes**, which are conceptually similar to
+//! [inodes] in Linux. A file system needs to implement
 //! the [`VfsOps`] trait, its files and directories need to implement the
 //! [`VfsNodeOps`] trait.
 //!
@@ -13,6 +13,26 @@
 //! - [`format()`](VfsOps::format): Format the filesystem.
 //! - [`statfs()`](VfsOps::statfs): Get the attributes of the filesystem.
 //! - [`root_dir()`](VfsOps::root_dir): Get root directory of the filesystem.
+//! - [`dev_id()`](VfsOps::dev_id): Get the device ID of the filesystem.
+//!
+//! A node opened with an offset and open flags is represented by a
+//! [`VfsFile`], which implements reading, writing and seeking on top of the
+//! node operations.
+//!
+//! Paths are resolved component by component with a [`PathResolver`], which
+//! follows symbolic links without recursion. Directories can implement the
+//! single-name operations of [`VfsDirOps`] instead of parsing paths, and get
+//! the path-taking ones with [`impl_vfs_dir_paths!`].
+//!
+//! With the `async` feature, the async counterparts `AsyncVfsOps` and
+//! `AsyncVfsNodeOps` are also provided, whose reads and writes wait for the
+//! node to be ready instead of blocking. Adapters convert nodes and
+//! filesystems between the blocking and async operations in both directions,
+//! the blocking ones wait with an executor that the kernel can provide with
+//! `set_executor()`.
+//!
+//! Node timestamps are taken from a clock source that the kernel can provide
+//! with [`set_clock_source()`].
 //!
 //! The [`VfsNodeOps`] trait provides the following operations on a file or a
 //! directory:
@@ -22,15 +42,41 @@
 //! | [`open()`](VfsNodeOps::open) | Do something when the node is opened | both |
 //! | [`release()`](VfsNodeOps::release) | Do something when the node is closed | both |
 //! | [`get_attr()`](VfsNodeOps::get_attr) | Get the attributes of the node | both |
+//! | [`set_attr()`](VfsNodeOps::set_attr) | Set the attributes of the node | both |
+//! | [`set_times()`](VfsNodeOps::set_times) | Set the access and modification times | both |
+//! | [`get_xattr()`](VfsNodeOps::get_xattr) | Get an extended attribute | both |
+//! | [`set_xattr()`](VfsNodeOps::set_xattr) | Set an extended attribute | both |
+//! | [`list_xattr()`](VfsNodeOps::list_xattr) | List names of extended attributes | both |
+//! | [`remove_xattr()`](VfsNodeOps::remove_xattr) | Remove an extended attribute | both |
+//! | [`lock_manager()`](VfsNodeOps::lock_manager) | Get the advisory lock manager | both |
 //! | [`read_at()`](VfsNodeOps::read_at) | Read data from the file | file |
 //! | [`write_at()`](VfsNodeOps::write_at) | Write data to the file | file |
+//! | [`read_vectored_at()`](VfsNodeOps::read_vectored_at) | Read data into multiple buffers | file |
+//! | [`write_vectored_at()`](VfsNodeOps::write_vectored_at) | Write data from multiple buffers | file |
+//! | [`append()`](VfsNodeOps::append) | Append data to the end of file | file |
+//! | [`copy_range()`](VfsNodeOps::copy_range) | Copy data from another file | file |
 //! | [`fsync()`](VfsNodeOps::fsync) | Synchronize the file data to disk | file |
 //! | [`truncate()`](VfsNodeOps::truncate) | Truncate the file | file |
+//! | [`fallocate()`](VfsNodeOps::fallocate) | Allocate or deallocate a range of the file | file |
+//! | [`seek_data()`](VfsNodeOps::seek_data) | Find the next data region of the file | file |
+//! | [`seek_hole()`](VfsNodeOps::seek_hole) | Find the next hole of the file | file |
+//! | [`ioctl()`](VfsNodeOps::ioctl) | Perform a device-specific command | file |
+//! | [`poll()`](VfsNodeOps::poll) | Poll the I/O readiness of the file | file |
+//! | [`get_pages()`](VfsNodeOps::get_pages) | Get the backing pages for memory mapping | file |
+//! | [`writeback_pages()`](VfsNodeOps::writeback_pages) | Write back dirty mapped pages | file |
+//! | [`readlink()`](VfsNodeOps::readlink) | Read the target of the symbolic link | symlink |
 //! | [`parent()`](VfsNodeOps::parent) | Get the parent directory | directory |
 //! | [`lookup()`](VfsNodeOps::lookup) | Lookup the node with the given path | directory |
+//! | [`lookup_nofollow()`](VfsNodeOps::lookup_nofollow) | Lookup without following the last symbolic link | directory |
+//! | [`lookup_as()`](VfsNodeOps::lookup_as) | Lookup with permission checks | directory |
 //! | [`create()`](VfsNodeOps::create) | Create a new node with the given path | directory |
+//! | [`mknod()`](VfsNodeOps::mknod) | Create a special file with the given path | directory |
+//! | [`symlink()`](VfsNodeOps::symlink) | Create a symbolic link with the given path | directory |
+//! | [`link()`](VfsNodeOps::link) | Create a hard link to an existing node | directory |
 //! | [`remove()`](VfsNodeOps::remove) | Remove the node with the given path | directory |
 //! | [`read_dir()`](VfsNodeOps::read_dir) | Read directory entries | directory |
+//! | [`rename()`](VfsNodeOps::rename) | Rename or move a node | directory |
+//! | [`rename_with_flags()`](VfsNodeOps::rename_with_flags) | Rename with `renameat2` flags | directory |
 //!
 //! [inodes]: https://en.wikipedia.org/wiki/Inode
 
@@ -38,15 +84,49 @@
 
 extern crate alloc;
 
+#[cfg(feature = "async")]
+mod async_ops;
+mod clock;
+mod copy;
+mod cred;
+mod dev;
+mod file;
+mod lock;
 mod macros;
+mod page;
+mod poll;
+mod resolve;
 mod structs;
+mod xattr;
 
+pub mod dir;
 pub mod path;
 
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 use axerrno::{ax_err, AxError, AxResult};
+use core::{task::Waker, time::Duration};
 
-pub use self::structs::{FileSystemInfo, VfsDirEntry, VfsNodeAttr, VfsNodePerm, VfsNodeType};
+#[cfg(feature = "async")]
+pub use self::async_ops::{poll_io, set_executor, to_async_node, to_sync_node, BlockOnFn};
+#[cfg(feature = "async")]
+pub use self::async_ops::{AsyncFsAdapter, AsyncNodeAdapter};
+#[cfg(feature = "async")]
+pub use self::async_ops::{AsyncVfsNodeOps, AsyncVfsNodeRef, AsyncVfsOps, VfsFuture};
+#[cfg(feature = "async")]
+pub use self::async_ops::{BlockingFsAdapter, BlockingNodeAdapter};
+pub use self::clock::{current_time, set_clock_source};
+pub use self::copy::{copy_buffer, generic_copy_range};
+pub use self::cred::{AccessMode, Credentials};
+pub use self::dev::{alloc_anon_dev, major, makedev, minor};
+pub use self::dir::VfsDirOps;
+pub use self::file::{OpenFlags, SeekFrom, VfsFile};
+pub use self::lock::{FileLock, FileLockKind, LockManager, LockOwner};
+pub use self::page::{FilePage, PageRef, PAGE_SIZE};
+pub use self::poll::{PollEvents, PollSet};
+pub use self::resolve::{LookupFn, PathResolver, SYMLOOP_MAX};
+pub use self::structs::{FallocateMode, FileSystemInfo, RenameFlags, VfsAttrMask, VfsDirEntry};
+pub use self::structs::{VfsMountFlags, VfsNodeAttr, VfsNodePerm, VfsNodeType, NAME_MAX};
+pub use self::xattr::{XattrFlags, XattrNamespace, XATTR_NAME_MAX, XATTR_SIZE_MAX};
 
 /// A wrapper of [`Arc<dyn VfsNodeOps>`].
 pub type VfsNodeRef = Arc<dyn VfsNodeOps>;
@@ -81,6 +161,13 @@ pub trait VfsOps: Send + Sync {
 
     /// Get the root directory of the filesystem.
     fn root_dir(&self) -> VfsNodeRef;
+
+    /// Get the device ID of the filesystem.
+    ///
+    /// It is reported as the `dev` of every node in the filesystem.
+    fn dev_id(&self) -> u64 {
+        0
+    }
 }
 
 /// Node (file/directory) operations.
@@ -100,18 +187,171 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Set the attributes of the node.
+    ///
+    /// Only the fields selected by `mask` are taken from `attr`, and the change
+    /// time is updated to the current time. Permission checks are left to the
+    /// caller.
+    fn set_attr(&self, _attr: &VfsNodeAttr, _mask: VfsAttrMask) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Set the last access and modification times of the node.
+    ///
+    /// A time is left unchanged if it is `None`. The default implementation
+    /// calls [`set_attr()`](VfsNodeOps::set_attr).
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        let mut attr = self.get_attr()?;
+        let mut mask = VfsAttrMask::empty();
+        if let Some(atime) = atime {
+            attr.set_atime(atime);
+            mask |= VfsAttrMask::ATIME;
+        }
+        if let Some(mtime) = mtime {
+            attr.set_mtime(mtime);
+            mask |= VfsAttrMask::MTIME;
+        }
+        self.set_attr(&attr, mask)
+    }
+
+    // extended attribute operations:
+
+    /// Get the value of the extended attribute `name` into `buf`.
+    ///
+    /// Return the size of the value. If `buf` is empty, only the size is
+    /// returned. Return [`VfsError::OutOfRange`] if `buf` is too small, and
+    /// [`VfsError::NotFound`] if the attribute does not exist.
+    fn get_xattr(&self, _name: &str, _buf: &mut [u8]) -> VfsResult<usize> {
+        ax_err!(OperationNotSupported)
+    }
+
+    /// Set the value of the extended attribute `name`.
+    ///
+    /// [`XattrFlags::CREATE`] fails with [`VfsError::AlreadyExists`] if the
+    /// attribute exists, and [`XattrFlags::REPLACE`] fails with
+    /// [`VfsError::NotFound`] if it does not.
+    fn set_xattr(&self, _name: &str, _value: &[u8], _flags: XattrFlags) -> VfsResult {
+        ax_err!(OperationNotSupported)
+    }
+
+    /// List the names of all extended attributes into `buf`, each followed by
+    /// a `\0`.
+    ///
+    /// Return the total size of the names. If `buf` is empty, only the size is
+    /// returned. Return [`VfsError::OutOfRange`] if `buf` is too small.
+    fn list_xattr(&self, _buf: &mut [u8]) -> VfsResult<usize> {
+        ax_err!(OperationNotSupported)
+    }
+
+    /// Remove the extended attribute `name`.
+    fn remove_xattr(&self, _name: &str) -> VfsResult {
+        ax_err!(OperationNotSupported)
+    }
+
+    /// Get the manager of `flock` and POSIX advisory locks on the node.
+    ///
+    /// Return `None` if the node does not support locking.
+    fn lock_manager(&self) -> Option<&LockManager> {
+        None
+    }
+
     // file operations:
 
     /// Read data from the file at the given offset.
+    ///
+    /// Nodes that are not ready, such as devices or pipes without available
+    /// data, return [`VfsError::WouldBlock`] instead of blocking.
     fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
         ax_err!(InvalidInput)
     }
 
     /// Write data to the file at the given offset.
+    ///
+    /// Nodes that are not ready return [`VfsError::WouldBlock`] instead of
+    /// blocking.
     fn write_at(&self, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
         ax_err!(InvalidInput)
     }
 
+    /// Read data from the file at the given offset into `bufs` in order,
+    /// and return the total number of bytes read.
+    ///
+    /// Implementations should read all buffers atomically with respect to
+    /// writers. The default implementation calls
+    /// [`read_at()`](VfsNodeOps::read_at) for each buffer, until a short
+    /// read happens.
+    fn read_vectored_at(&self, offset: u64, bufs: &mut [&mut [u8]]) -> VfsResult<usize> {
+        let mut total = 0;
+        for buf in bufs.iter_mut() {
+            let len = match self.read_at(offset + total as u64, buf) {
+                Ok(len) => len,
+                Err(_) if total > 0 => break,
+                Err(e) => return Err(e),
+            };
+            total += len;
+            if len < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Write data from `bufs` in order to the file at the given offset, and
+    /// return the total number of bytes written.
+    ///
+    /// Implementations should write all buffers atomically with respect to
+    /// other readers and writers. The default implementation calls
+    /// [`write_at()`](VfsNodeOps::write_at) for each buffer, until a short
+    /// write happens.
+    fn write_vectored_at(&self, offset: u64, bufs: &[&[u8]]) -> VfsResult<usize> {
+        let mut total = 0;
+        for buf in bufs {
+            let len = match self.write_at(offset + total as u64, buf) {
+                Ok(len) => len,
+                Err(_) if total > 0 => break,
+                Err(e) => return Err(e),
+            };
+            total += len;
+            if len < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Append data from `bufs` in order to the end of file, as writes with
+    /// `O_APPEND` do, and return the offset written at and the total number
+    /// of bytes written.
+    ///
+    /// Implementations should find the end of file and write atomically, so
+    /// that concurrent appends never overwrite each other. The default
+    /// implementation calls [`get_attr()`](VfsNodeOps::get_attr) and
+    /// [`write_vectored_at()`](VfsNodeOps::write_vectored_at), which is not
+    /// atomic.
+    fn append(&self, bufs: &[&[u8]]) -> VfsResult<(u64, usize)> {
+        let offset = self.get_attr()?.size();
+        Ok((offset, self.write_vectored_at(offset, bufs)?))
+    }
+
+    /// Copy `len` bytes of the file `src` at `src_offset` to this file at
+    /// `offset`, as `copy_file_range` does.
+    ///
+    /// Return the number of bytes copied, which is less than `len` if the
+    /// end of `src` is reached. Filesystems can copy without a buffer, or
+    /// share the data between the files until either is written.
+    ///
+    /// The default implementation copies through a buffer with
+    /// [`generic_copy_range()`].
+    fn copy_range(
+        &self,
+        offset: u64,
+        src: &VfsNodeRef,
+        src_offset: u64,
+        len: u64,
+    ) -> VfsResult<u64> {
+        generic_copy_range(self, offset, src, src_offset, len)
+    }
+
     /// Flush the file, synchronize the data to disk.
     fn fsync(&self) -> VfsResult {
         ax_err!(InvalidInput)
@@ -122,6 +362,86 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(InvalidInput)
     }
 
+    /// Manipulate the space of the file range `[offset, offset + len)`
+    /// according to `mode`, as `fallocate` does.
+    ///
+    /// [`FallocateMode::COLLAPSE_RANGE`] and [`FallocateMode::INSERT_RANGE`]
+    /// require a range aligned to the block size of the filesystem.
+    fn fallocate(&self, _mode: FallocateMode, _offset: u64, _len: u64) -> VfsResult {
+        ax_err!(OperationNotSupported)
+    }
+
+    /// Find the start of the next data region at or after `offset`, as
+    /// `SEEK_DATA` does.
+    ///
+    /// Return `None` if there is no data at or after `offset`. The default
+    /// implementation treats the whole file as data.
+    fn seek_data(&self, offset: u64) -> VfsResult<Option<u64>> {
+        let size = self.get_attr()?.size();
+        Ok((offset < size).then_some(offset))
+    }
+
+    /// Find the start of the next hole at or after `offset`, as `SEEK_HOLE`
+    /// does. There is always an implicit hole at the end of file.
+    ///
+    /// Return `None` if `offset` is beyond the end of file. The default
+    /// implementation treats the whole file as data.
+    fn seek_hole(&self, offset: u64) -> VfsResult<Option<u64>> {
+        let size = self.get_attr()?.size();
+        Ok((offset < size).then_some(size))
+    }
+
+    /// Perform the device-specific command `cmd`, as `ioctl` does.
+    ///
+    /// `arg` holds the argument of the command, and the output of the command
+    /// is written back to it. Return a command-specific non-negative value,
+    /// or [`VfsError::NotATty`] if the command is not supported by the node.
+    fn ioctl(&self, _cmd: u32, _arg: &mut [u8]) -> VfsResult<usize> {
+        ax_err!(NotATty)
+    }
+
+    /// Poll the I/O readiness of the file.
+    ///
+    /// Return the events that are ready now, among `events` and
+    /// [`PollEvents::ALWAYS`]. If none of them is ready and `waker` is given,
+    /// it is registered to be woken when they may become ready, and the
+    /// caller should poll again after that.
+    ///
+    /// The default implementation reports the file as always readable and
+    /// writable.
+    fn poll(&self, events: PollEvents, _waker: Option<&Waker>) -> VfsResult<PollEvents> {
+        Ok(events & (PollEvents::IN | PollEvents::OUT))
+    }
+
+    /// Get the pages backing the file range `[offset, offset + len)`, which
+    /// must be aligned to [`PAGE_SIZE`].
+    ///
+    /// The returned pages are shared with the file, so they can be mapped to
+    /// implement shared file mappings without copying. They stay valid while
+    /// referenced, and keep backing the same range of the file even if it is
+    /// truncated, in which case the part beyond the end of file reads as
+    /// zeros. Fewer pages are returned if the range extends beyond the end of
+    /// file.
+    fn get_pages(&self, _offset: u64, _len: u64) -> VfsResult<Vec<PageRef>> {
+        ax_err!(Unsupported)
+    }
+
+    /// Write back the dirty pages in the file range `[offset, offset + len)`,
+    /// which have been modified through mappings.
+    fn writeback_pages(&self, _offset: u64, _len: u64) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    // symbolic link operations:
+
+    /// Read the target path of the symbolic link into `buf`.
+    ///
+    /// Return the number of bytes read, the target is truncated if `buf` is
+    /// too small.
+    fn readlink(&self, _buf: &mut [u8]) -> VfsResult<usize> {
+        ax_err!(InvalidInput)
+    }
+
     // directory operations:
 
     /// Get the parent directory of this directory.
@@ -133,11 +453,43 @@ pub trait VfsNodeOps: Send + Sync {
 
     /// Lookup the node with given `path` in the directory.
     ///
-    /// Return the node if found.
+    /// Symbolic links are followed, including the last component. Return the
+    /// node if found.
     fn lookup(self: Arc<Self>, _path: &str) -> VfsResult<VfsNodeRef> {
         ax_err!(Unsupported)
     }
 
+    /// Lookup the node with given `path` in the directory, without following
+    /// the symbolic link at the last component.
+    ///
+    /// Symbolic links in the intermediate components are still followed. The
+    /// default implementation calls [`lookup()`](VfsNodeOps::lookup).
+    fn lookup_nofollow(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        self.lookup(path)
+    }
+
+    /// Lookup the node with given `path` in the directory, checking that
+    /// `cred` can search every directory walked through.
+    ///
+    /// The symbolic link at the last component is followed if `follow` is
+    /// `true`. Return [`VfsError::PermissionDenied`] if the search permission
+    /// is denied. The default implementation only checks this directory, and
+    /// then calls [`lookup()`](VfsNodeOps::lookup) or
+    /// [`lookup_nofollow()`](VfsNodeOps::lookup_nofollow).
+    fn lookup_as(
+        self: Arc<Self>,
+        path: &str,
+        cred: &Credentials,
+        follow: bool,
+    ) -> VfsResult<VfsNodeRef> {
+        cred.check_access(&self.get_attr()?, AccessMode::EXECUTE)?;
+        if follow {
+            self.lookup(path)
+        } else {
+            self.lookup_nofollow(path)
+        }
+    }
+
     /// Create a new node with the given `path` in the directory
     ///
     /// Return [`Ok(())`](Ok) if it already exists.
@@ -145,13 +497,43 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Create a special file with the given `path` in the directory, as
+    /// `mknod` does.
+    ///
+    /// `ty` and `perm` are the type and the permission of the node, and
+    /// `rdev` is the device ID for [`VfsNodeType::CharDevice`] and
+    /// [`VfsNodeType::BlockDevice`], made with [`makedev()`]. Return
+    /// [`VfsError::AlreadyExists`] if the node exists.
+    fn mknod(&self, _path: &str, _ty: VfsNodeType, _perm: VfsNodePerm, _rdev: u64) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a symbolic link with the given `path` in the directory, which
+    /// points to `target`.
+    fn symlink(&self, _path: &str, _target: &str) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
+    /// Create a hard link with the given `path` in the directory, which refers
+    /// to the existing `node`.
+    fn link(&self, _path: &str, _node: &VfsNodeRef) -> VfsResult {
+        ax_err!(Unsupported)
+    }
+
     /// Remove the node with the given `path` in the directory.
     fn remove(&self, _path: &str) -> VfsResult {
         ax_err!(Unsupported)
     }
 
-    /// Read directory entries into `dirents`, starting from `start_idx`.
-    fn read_dir(&self, _start_idx: usize, _dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+    /// Read directory entries into `dirents`, starting from the position
+    /// `cookie`, and return the number of entries read.
+    ///
+    /// The cookie is 0 for the beginning of the directory, otherwise it is
+    /// the [`offset()`](VfsDirEntry::offset) of the last entry read. It is an
+    /// opaque value rather than an index, so that an entry present during
+    /// the whole listing is returned exactly once, even if other entries are
+    /// added or removed in between.
+    fn read_dir(&self, _cookie: u64, _dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
         ax_err!(Unsupported)
     }
 
@@ -160,6 +542,18 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Renames or moves existing file or directory with `flags`, as
+    /// `renameat2` does.
+    ///
+    /// The default implementation only supports empty `flags`, and calls
+    /// [`rename()`](Self::rename).
+    fn rename_with_flags(&self, src_path: &str, dst_path: &str, flags: RenameFlags) -> VfsResult {
+        if !flags.is_empty() {
+            return ax_err!(InvalidInput);
+        }
+        self.rename(src_path, dst_path)
+    }
+
     /// Convert `&self` to [`&dyn Any`][1] that can use
     /// [`Any::downcast_ref`][2].
     ///
@@ -168,10 +562,20 @@ pub trait VfsNodeOps: Send + Sync {
     fn as_any(&self) -> &dyn core::any::Any {
         unimplemented!()
     }
+
+    /// Get the native async operations of the node, if it also implements
+    /// [`AsyncVfsNodeOps`].
+    ///
+    /// Return `None` by default, then [`to_async_node()`] wraps the node in
+    /// an [`AsyncNodeAdapter`].
+    #[cfg(feature = "async")]
+    fn into_async(self: Arc<Self>) -> Option<AsyncVfsNodeRef> {
+        None
+    }
 }
 
 #[doc(hidden)]
 pub mod __priv {
-    pub use alloc::sync::Arc;
+    pub use alloc::{boxed::Box, sync::Arc, vec::Vec};
     pub use axerrno::ax_err;
 }
@@ -0,0 +1,362 @@
+use alloc::vec::Vec;
+use core::task::Waker;
+
+use spin::{Mutex, MutexGuard};
+
+use crate::{VfsError, VfsResult};
+
+/// Identifies the owner of a lock.
+///
+/// For `flock` locks it is usually the open file description, and for POSIX
+/// locks it is usually the process ID.
+pub type LockOwner = usize;
+
+/// Kind of an advisory lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLockKind {
+    /// A shared (read) lock, which can be held by multiple owners.
+    Shared,
+    /// An exclusive (write) lock, which can only be held by one owner.
+    Exclusive,
+}
+
+/// A POSIX byte-range lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLock {
+    /// Kind of the lock.
+    pub kind: FileLockKind,
+    /// Start offset of the range.
+    pub start: u64,
+    /// End offset of the range (exclusive), [`u64::MAX`] if the range extends
+    /// to the end of file.
+    pub end: u64,
+    /// Owner of the lock.
+    pub owner: LockOwner,
+}
+
+impl FileLock {
+    /// Creates a lock of `len` bytes from `start`, where a `len` of 0 means
+    /// the lock extends to the end of file, as in `fcntl`.
+    ///
+    /// Returns [`VfsError::InvalidInput`] if the range overflows.
+    pub fn new(kind: FileLockKind, start: u64, len: u64, owner: LockOwner) -> VfsResult<Self> {
+        Ok(Self {
+            kind,
+            start,
+            end: range_end(start, len)?,
+            owner,
+        })
+    }
+
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end
+    }
+
+    fn conflicts_with(&self, other: &FileLock) -> bool {
+        self.owner != other.owner
+            && self.overlaps(other.start, other.end)
+            && (self.kind == FileLockKind::Exclusive || other.kind == FileLockKind::Exclusive)
+    }
+}
+
+/// Returns the end of the range of `len` bytes from `start`, where a `len`
+/// of 0 means the end of file.
+fn range_end(start: u64, len: u64) -> VfsResult<u64> {
+    match len {
+        0 => Ok(u64::MAX),
+        _ => start.checked_add(len).ok_or(VfsError::InvalidInput),
+    }
+}
+
+#[derive(Default)]
+struct LockState {
+    flocks: Vec<(LockOwner, FileLockKind)>,
+    posix: Vec<FileLock>,
+    waiters: Vec<Waker>,
+}
+
+impl LockState {
+    /// Registers `waker` to be woken when a lock is released, if given, and
+    /// returns [`VfsError::WouldBlock`].
+    fn wait(&mut self, waker: Option<&Waker>) -> VfsResult {
+        if let Some(waker) = waker {
+            if !self.waiters.iter().any(|w| w.will_wake(waker)) {
+                self.waiters.push(waker.clone());
+            }
+        }
+        Err(VfsError::WouldBlock)
+    }
+
+    fn flock_conflicts(&self, owner: LockOwner, kind: FileLockKind) -> bool {
+        self.flocks.iter().any(|&(o, k)| {
+            o != owner && (kind == FileLockKind::Exclusive || k == FileLockKind::Exclusive)
+        })
+    }
+
+    /// Removes the range `[start, end)` from the POSIX locks of `owner`,
+    /// splitting the locks partially covered.
+    fn posix_carve(&mut self, owner: LockOwner, start: u64, end: u64) {
+        let mut split = Vec::new();
+        self.posix.retain(|lock| {
+            if lock.owner != owner || !lock.overlaps(start, end) {
+                return true;
+            }
+            if lock.start < start {
+                split.push(FileLock {
+                    end: start,
+                    ..*lock
+                });
+            }
+            if lock.end > end {
+                split.push(FileLock {
+                    start: end,
+                    ..*lock
+                });
+            }
+            false
+        });
+        self.posix.extend(split);
+    }
+}
+
+/// Advisory lock manager of a node.
+///
+/// It keeps both whole-file `flock` locks and POSIX byte-range locks, which
+/// do not interact with each other. A filesystem embeds one in each node that
+/// supports locking, and returns it in
+/// [`VfsNodeOps::lock_manager`](crate::VfsNodeOps::lock_manager).
+///
+/// Locks are not released automatically, the caller should unlock them when
+/// the file is closed or the process exits.
+///
+/// Requests never block. A request that conflicts with the locks of other
+/// owners fails with [`VfsError::WouldBlock`], and registers the given waker,
+/// which is woken when any lock is released or converted. A blocking request
+/// (`LOCK_EX` without `LOCK_NB`, or `F_SETLKW`) waits for the waker and then
+/// tries again.
+#[derive(Default)]
+pub struct LockManager {
+    state: Mutex<LockState>,
+}
+
+impl LockManager {
+    /// Creates an empty lock manager.
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(LockState {
+                flocks: Vec::new(),
+                posix: Vec::new(),
+                waiters: Vec::new(),
+            }),
+        }
+    }
+
+    /// Wakes all waiters after the locks in `state` are updated, as some may
+    /// have been released.
+    fn wake_waiters(mut state: MutexGuard<LockState>) {
+        let waiters = core::mem::take(&mut state.waiters);
+        drop(state);
+        // wake outside the lock, the woken task may request again
+        waiters.into_iter().for_each(Waker::wake);
+    }
+
+    /// Acquires a whole-file lock for `owner`, or converts the lock it already
+    /// holds to `kind`.
+    ///
+    /// Returns [`VfsError::WouldBlock`] if the lock conflicts with the ones of
+    /// other owners, and registers `waker` to be woken when it may succeed.
+    pub fn flock(&self, owner: LockOwner, kind: FileLockKind, waker: Option<&Waker>) -> VfsResult {
+        let mut state = self.state.lock();
+        if state.flock_conflicts(owner, kind) {
+            return state.wait(waker);
+        }
+        match state.flocks.iter_mut().find(|(o, _)| *o == owner) {
+            Some(lock) => lock.1 = kind,
+            None => state.flocks.push((owner, kind)),
+        }
+        Self::wake_waiters(state);
+        Ok(())
+    }
+
+    /// Releases the whole-file lock of `owner`, if any.
+    pub fn funlock(&self, owner: LockOwner) {
+        let mut state = self.state.lock();
+        state.flocks.retain(|(o, _)| *o != owner);
+        Self::wake_waiters(state);
+    }
+
+    /// Returns the first POSIX lock of other owners that conflicts with
+    /// `lock`, as `F_GETLK` does.
+    pub fn test_lock(&self, lock: &FileLock) -> Option<FileLock> {
+        let state = self.state.lock();
+        state.posix.iter().find(|l| l.conflicts_with(lock)).copied()
+    }
+
+    /// Acquires a POSIX byte-range lock.
+    ///
+    /// Locks of the same owner over the range are replaced, and adjacent
+    /// locks of the same kind are merged. Returns [`VfsError::WouldBlock`] if
+    /// the lock conflicts with the ones of other owners, and registers `waker`
+    /// to be woken when it may succeed.
+    pub fn lock(&self, lock: FileLock, waker: Option<&Waker>) -> VfsResult {
+        let mut state = self.state.lock();
+        if state.posix.iter().any(|l| l.conflicts_with(&lock)) {
+            return state.wait(waker);
+        }
+        let mut new = lock;
+        for l in state.posix.iter() {
+            if l.owner == lock.owner
+                && l.kind == lock.kind
+                && l.start <= lock.end
+                && lock.start <= l.end
+            {
+                new.start = new.start.min(l.start);
+                new.end = new.end.max(l.end);
+            }
+        }
+        state.posix_carve(new.owner, new.start, new.end);
+        state.posix.push(new);
+        state.posix.sort_by_key(|l| (l.start, l.owner));
+        Self::wake_waiters(state);
+        Ok(())
+    }
+
+    /// Releases the POSIX locks of `owner` in the range of `len` bytes from
+    /// `start`, where a `len` of 0 means the end of file, as in `fcntl`.
+    ///
+    /// Returns [`VfsError::InvalidInput`] if the range overflows.
+    pub fn unlock(&self, owner: LockOwner, start: u64, len: u64) -> VfsResult {
+        let end = range_end(start, len)?;
+        let mut state = self.state.lock();
+        state.posix_carve(owner, start, end);
+        state.posix.sort_by_key(|l| (l.start, l.owner));
+        Self::wake_waiters(state);
+        Ok(())
+    }
+
+    /// Releases all POSIX locks of `owner`.
+    pub fn unlock_all(&self, owner: LockOwner) {
+        let mut state = self.state.lock();
+        state.posix.retain(|l| l.owner != owner);
+        Self::wake_waiters(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileLockKind::*;
+    use super::*;
+    use alloc::{sync::Arc, task::Wake};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountWaker(AtomicUsize);
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn lock(kind: FileLockKind, start: u64, len: u64, owner: LockOwner) -> FileLock {
+        FileLock::new(kind, start, len, owner).unwrap()
+    }
+
+    /// Returns the POSIX locks of `owner` as `(kind, start, end)`.
+    fn ranges(locks: &LockManager, owner: LockOwner) -> Vec<(FileLockKind, u64, u64)> {
+        let state = locks.state.lock();
+        let owned = state.posix.iter().filter(|l| l.owner == owner);
+        owned.map(|l| (l.kind, l.start, l.end)).collect()
+    }
+
+    #[test]
+    fn test_lock_range() {
+        assert_eq!(lock(Shared, 10, 0, 1).end, u64::MAX);
+        assert_eq!(lock(Shared, 10, 5, 1).end, 15);
+        assert!(FileLock::new(Shared, u64::MAX - 1, 2, 1).is_err());
+        let locks = LockManager::new();
+        assert_eq!(locks.unlock(1, u64::MAX, 1), Err(VfsError::InvalidInput));
+    }
+
+    #[test]
+    fn test_lock_conflict() {
+        let locks = LockManager::new();
+        locks.lock(lock(Shared, 0, 10, 1), None).unwrap();
+        locks.lock(lock(Shared, 5, 10, 2), None).unwrap();
+        assert_eq!(
+            locks.lock(lock(Exclusive, 9, 1, 3), None),
+            Err(VfsError::WouldBlock)
+        );
+        locks.lock(lock(Exclusive, 15, 0, 3), None).unwrap();
+        // the own locks never conflict
+        locks.lock(lock(Exclusive, 0, 5, 1), None).unwrap();
+        assert_eq!(
+            locks.test_lock(&lock(Shared, 0, 5, 2)),
+            Some(lock(Exclusive, 0, 5, 1))
+        );
+        assert_eq!(locks.test_lock(&lock(Shared, 5, 10, 4)), None);
+
+        locks.flock(1, Shared, None).unwrap();
+        locks.flock(2, Shared, None).unwrap();
+        assert_eq!(locks.flock(3, Exclusive, None), Err(VfsError::WouldBlock));
+        assert_eq!(locks.flock(1, Exclusive, None), Err(VfsError::WouldBlock));
+        locks.funlock(2);
+        locks.flock(1, Exclusive, None).unwrap();
+    }
+
+    #[test]
+    fn test_lock_merge_split() {
+        let locks = LockManager::new();
+        locks.lock(lock(Shared, 0, 10, 1), None).unwrap();
+        locks.lock(lock(Shared, 10, 10, 1), None).unwrap();
+        locks.lock(lock(Shared, 30, 10, 1), None).unwrap();
+        assert_eq!(ranges(&locks, 1), [(Shared, 0, 20), (Shared, 30, 40)]);
+        locks.lock(lock(Shared, 15, 20, 1), None).unwrap();
+        assert_eq!(ranges(&locks, 1), [(Shared, 0, 40)]);
+
+        // a lock of another kind splits the existing one
+        locks.lock(lock(Exclusive, 10, 10, 1), None).unwrap();
+        assert_eq!(
+            ranges(&locks, 1),
+            [(Shared, 0, 10), (Exclusive, 10, 20), (Shared, 20, 40)]
+        );
+        locks.unlock(1, 5, 30).unwrap();
+        assert_eq!(ranges(&locks, 1), [(Shared, 0, 5), (Shared, 35, 40)]);
+        locks.unlock(1, 0, 0).unwrap();
+        assert!(ranges(&locks, 1).is_empty());
+    }
+
+    #[test]
+    fn test_lock_wake() {
+        let locks = LockManager::new();
+        let count = Arc::new(CountWaker::default());
+        let waker = Waker::from(count.clone());
+        locks.lock(lock(Exclusive, 0, 0, 1), None).unwrap();
+        locks.flock(1, Exclusive, None).unwrap();
+
+        let wanted = lock(Shared, 10, 10, 2);
+        assert_eq!(locks.lock(wanted, Some(&waker)), Err(VfsError::WouldBlock));
+        assert_eq!(locks.lock(wanted, Some(&waker)), Err(VfsError::WouldBlock));
+        assert_eq!(
+            locks.flock(2, Shared, Some(&waker)),
+            Err(VfsError::WouldBlock)
+        );
+        // releasing a range the waiter does not want still wakes it
+        locks.unlock(1, 30, 0).unwrap();
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+        assert_eq!(locks.lock(wanted, Some(&waker)), Err(VfsError::WouldBlock));
+        locks.unlock(1, 0, 20).unwrap();
+        assert_eq!(count.0.load(Ordering::SeqCst), 2);
+        locks.lock(wanted, Some(&waker)).unwrap();
+
+        assert_eq!(
+            locks.flock(2, Shared, Some(&waker)),
+            Err(VfsError::WouldBlock)
+        );
+        locks.flock(1, Shared, None).unwrap();
+        assert_eq!(count.0.load(Ordering::SeqCst), 3);
+        locks.flock(2, Shared, Some(&waker)).unwrap();
+        locks.funlock(1);
+        assert_eq!(count.0.load(Ordering::SeqCst), 3);
+    }
+}
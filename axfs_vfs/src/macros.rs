@@ -46,13 +46,31 @@ macro_rules! impl_vfs_non_dir_default {
             $crate::__priv::ax_err!(NotADirectory)
         }
 
+        fn mknod(
+            &self,
+            _path: &str,
+            _ty: $crate::VfsNodeType,
+            _perm: $crate::VfsNodePerm,
+            _rdev: u64,
+        ) -> $crate::VfsResult {
+            $crate::__priv::ax_err!(NotADirectory)
+        }
+
+        fn symlink(&self, _path: &str, _target: &str) -> $crate::VfsResult {
+            $crate::__priv::ax_err!(NotADirectory)
+        }
+
+        fn link(&self, _path: &str, _node: &$crate::VfsNodeRef) -> $crate::VfsResult {
+            $crate::__priv::ax_err!(NotADirectory)
+        }
+
         fn remove(&self, _path: &str) -> $crate::VfsResult {
             $crate::__priv::ax_err!(NotADirectory)
         }
 
         fn read_dir(
             &self,
-            _start_idx: usize,
+            _cookie: u64,
             _dirents: &mut [$crate::VfsDirEntry],
         ) -> $crate::VfsResult<usize> {
             $crate::__priv::ax_err!(NotADirectory)
@@ -64,3 +82,285 @@ macro_rules! impl_vfs_non_dir_default {
         }
     };
 }
+
+/// When implement [`VfsNodeOps`] on a directory node that implements
+/// [`VfsDirOps`], add the path-taking directory operations built on the
+/// single-name ones.
+///
+/// [`VfsNodeOps`]: crate::VfsNodeOps
+/// [`VfsDirOps`]: crate::VfsDirOps
+#[macro_export]
+macro_rules! impl_vfs_dir_paths {
+    () => {
+        fn lookup(
+            self: $crate::__priv::Arc<Self>,
+            path: &str,
+        ) -> $crate::VfsResult<$crate::VfsNodeRef> {
+            $crate::dir::lookup(&*self, path, true, None)
+        }
+
+        fn lookup_nofollow(
+            self: $crate::__priv::Arc<Self>,
+            path: &str,
+        ) -> $crate::VfsResult<$crate::VfsNodeRef> {
+            $crate::dir::lookup(&*self, path, false, None)
+        }
+
+        fn lookup_as(
+            self: $crate::__priv::Arc<Self>,
+            path: &str,
+            cred: &$crate::Credentials,
+            follow: bool,
+        ) -> $crate::VfsResult<$crate::VfsNodeRef> {
+            $crate::dir::lookup(&*self, path, follow, Some(cred))
+        }
+
+        fn create(&self, path: &str, ty: $crate::VfsNodeType) -> $crate::VfsResult {
+            $crate::dir::create(self, path, ty)
+        }
+
+        fn mknod(
+            &self,
+            path: &str,
+            ty: $crate::VfsNodeType,
+            perm: $crate::VfsNodePerm,
+            rdev: u64,
+        ) -> $crate::VfsResult {
+            $crate::dir::mknod(self, path, ty, perm, rdev)
+        }
+
+        fn symlink(&self, path: &str, target: &str) -> $crate::VfsResult {
+            $crate::dir::symlink(self, path, target)
+        }
+
+        fn link(&self, path: &str, node: &$crate::VfsNodeRef) -> $crate::VfsResult {
+            $crate::dir::link(self, path, node)
+        }
+
+        fn remove(&self, path: &str) -> $crate::VfsResult {
+            $crate::dir::remove(self, path)
+        }
+
+        fn rename(&self, src_path: &str, dst_path: &str) -> $crate::VfsResult {
+            $crate::dir::rename(self, src_path, dst_path, $crate::RenameFlags::empty())
+        }
+
+        fn rename_with_flags(
+            &self,
+            src_path: &str,
+            dst_path: &str,
+            flags: $crate::RenameFlags,
+        ) -> $crate::VfsResult {
+            $crate::dir::rename(self, src_path, dst_path, flags)
+        }
+    };
+}
+
+/// When implement [`AsyncVfsNodeOps`] on a node that also implements
+/// [`VfsNodeOps`] and never waits, add async operations that run the blocking
+/// ones, so both report the same results and errors.
+///
+/// [`VfsNodeOps`]: crate::VfsNodeOps
+/// [`AsyncVfsNodeOps`]: crate::AsyncVfsNodeOps
+#[cfg(feature = "async")]
+#[macro_export]
+macro_rules! impl_vfs_async_from_sync {
+    () => {
+        fn open(&self) -> $crate::VfsFuture<'_> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::open(self) })
+        }
+
+        fn release(&self) -> $crate::VfsFuture<'_> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::release(self) })
+        }
+
+        fn get_attr(&self) -> $crate::VfsFuture<'_, $crate::VfsNodeAttr> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::get_attr(self) })
+        }
+
+        fn set_attr<'a>(
+            &'a self,
+            attr: &'a $crate::VfsNodeAttr,
+            mask: $crate::VfsAttrMask,
+        ) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::set_attr(self, attr, mask) })
+        }
+
+        fn get_xattr<'a>(
+            &'a self,
+            name: &'a str,
+            buf: &'a mut [u8],
+        ) -> $crate::VfsFuture<'a, usize> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::get_xattr(self, name, buf) })
+        }
+
+        fn set_xattr<'a>(
+            &'a self,
+            name: &'a str,
+            value: &'a [u8],
+            flags: $crate::XattrFlags,
+        ) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::set_xattr(self, name, value, flags)
+            })
+        }
+
+        fn list_xattr<'a>(&'a self, buf: &'a mut [u8]) -> $crate::VfsFuture<'a, usize> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::list_xattr(self, buf) })
+        }
+
+        fn remove_xattr<'a>(&'a self, name: &'a str) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::remove_xattr(self, name) })
+        }
+
+        fn lock_manager(&self) -> Option<&$crate::LockManager> {
+            $crate::VfsNodeOps::lock_manager(self)
+        }
+
+        fn read_at<'a>(&'a self, offset: u64, buf: &'a mut [u8]) -> $crate::VfsFuture<'a, usize> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::read_at(self, offset, buf) })
+        }
+
+        fn write_at<'a>(&'a self, offset: u64, buf: &'a [u8]) -> $crate::VfsFuture<'a, usize> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::write_at(self, offset, buf) })
+        }
+
+        fn fsync(&self) -> $crate::VfsFuture<'_> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::fsync(self) })
+        }
+
+        fn truncate(&self, size: u64) -> $crate::VfsFuture<'_> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::truncate(self, size) })
+        }
+
+        fn fallocate(
+            &self,
+            mode: $crate::FallocateMode,
+            offset: u64,
+            len: u64,
+        ) -> $crate::VfsFuture<'_> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::fallocate(self, mode, offset, len)
+            })
+        }
+
+        fn ioctl<'a>(&'a self, cmd: u32, arg: &'a mut [u8]) -> $crate::VfsFuture<'a, usize> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::ioctl(self, cmd, arg) })
+        }
+
+        fn poll(
+            &self,
+            events: $crate::PollEvents,
+            waker: Option<&::core::task::Waker>,
+        ) -> $crate::VfsResult<$crate::PollEvents> {
+            $crate::VfsNodeOps::poll(self, events, waker)
+        }
+
+        fn get_pages(
+            &self,
+            offset: u64,
+            len: u64,
+        ) -> $crate::VfsFuture<'_, $crate::__priv::Vec<$crate::PageRef>> {
+            $crate::__priv::Box::pin(
+                async move { $crate::VfsNodeOps::get_pages(self, offset, len) },
+            )
+        }
+
+        fn writeback_pages(&self, offset: u64, len: u64) -> $crate::VfsFuture<'_> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::writeback_pages(self, offset, len)
+            })
+        }
+
+        fn readlink<'a>(&'a self, buf: &'a mut [u8]) -> $crate::VfsFuture<'a, usize> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::readlink(self, buf) })
+        }
+
+        fn parent(&self) -> Option<$crate::AsyncVfsNodeRef> {
+            $crate::VfsNodeOps::parent(self).map($crate::to_async_node)
+        }
+
+        fn lookup<'a>(
+            self: $crate::__priv::Arc<Self>,
+            path: &'a str,
+        ) -> $crate::VfsFuture<'a, $crate::AsyncVfsNodeRef> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::lookup(self, path).map($crate::to_async_node)
+            })
+        }
+
+        fn lookup_nofollow<'a>(
+            self: $crate::__priv::Arc<Self>,
+            path: &'a str,
+        ) -> $crate::VfsFuture<'a, $crate::AsyncVfsNodeRef> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::lookup_nofollow(self, path).map($crate::to_async_node)
+            })
+        }
+
+        fn create<'a>(&'a self, path: &'a str, ty: $crate::VfsNodeType) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::create(self, path, ty) })
+        }
+
+        fn mknod<'a>(
+            &'a self,
+            path: &'a str,
+            ty: $crate::VfsNodeType,
+            perm: $crate::VfsNodePerm,
+            rdev: u64,
+        ) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::mknod(self, path, ty, perm, rdev)
+            })
+        }
+
+        fn symlink<'a>(&'a self, path: &'a str, target: &'a str) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::symlink(self, path, target) })
+        }
+
+        fn link<'a>(
+            &'a self,
+            path: &'a str,
+            node: &'a $crate::AsyncVfsNodeRef,
+        ) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::link(self, path, &$crate::to_sync_node(node.clone()))
+            })
+        }
+
+        fn remove<'a>(&'a self, path: &'a str) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move { $crate::VfsNodeOps::remove(self, path) })
+        }
+
+        fn read_dir<'a>(
+            &'a self,
+            cookie: u64,
+            dirents: &'a mut [$crate::VfsDirEntry],
+        ) -> $crate::VfsFuture<'a, usize> {
+            $crate::__priv::Box::pin(
+                async move { $crate::VfsNodeOps::read_dir(self, cookie, dirents) },
+            )
+        }
+
+        fn rename<'a>(&'a self, src_path: &'a str, dst_path: &'a str) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(
+                async move { $crate::VfsNodeOps::rename(self, src_path, dst_path) },
+            )
+        }
+
+        fn rename_with_flags<'a>(
+            &'a self,
+            src_path: &'a str,
+            dst_path: &'a str,
+            flags: $crate::RenameFlags,
+        ) -> $crate::VfsFuture<'a> {
+            $crate::__priv::Box::pin(async move {
+                $crate::VfsNodeOps::rename_with_flags(self, src_path, dst_path, flags)
+            })
+        }
+
+        fn into_sync(self: $crate::__priv::Arc<Self>) -> Option<$crate::VfsNodeRef> {
+            Some(self)
+        }
+    };
+}
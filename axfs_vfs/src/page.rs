@@ -0,0 +1,139 @@
+use alloc::{boxed::Box, sync::Arc};
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Size of a [`FilePage`] in bytes.
+pub const PAGE_SIZE: usize = 4096;
+
+/// A shared reference to a [`FilePage`].
+pub type PageRef = Arc<FilePage>;
+
+#[repr(C, align(4096))]
+struct PageData(UnsafeCell<[u8; PAGE_SIZE]>);
+
+/// A page of file data, which can be mapped into address spaces directly.
+///
+/// The page is page-aligned and never moves, so its address can be mapped
+/// with [`as_ptr()`](FilePage::as_ptr). While mapped, the content can be
+/// changed by the mapping at any time, and the kernel should call
+/// [`set_dirty()`](FilePage::set_dirty) when it finds the page written.
+///
+/// The page is shared by [`PageRef`]s, so the methods changing its content
+/// are `unsafe`: the filesystem owning the page must serialize them with
+/// other accesses, usually by the lock of the file contents.
+pub struct FilePage {
+    data: Box<PageData>,
+    dirty: AtomicBool,
+}
+
+unsafe impl Send for FilePage {}
+unsafe impl Sync for FilePage {}
+
+impl FilePage {
+    /// Allocates a zero-filled page.
+    pub fn new() -> Self {
+        Self {
+            // SAFETY: all zero bytes are a valid page content. The page is
+            // allocated directly on the heap, instead of built on the stack
+            // and moved there.
+            data: unsafe { Box::<PageData>::new_zeroed().assume_init() },
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the address of the page content.
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.data.0.get().cast()
+    }
+
+    /// Reads from the page at `offset` into `buf`, and returns the number of
+    /// bytes read, which is 0 if `offset` is beyond the page.
+    pub fn read(&self, offset: usize, buf: &mut [u8]) -> usize {
+        if offset >= PAGE_SIZE {
+            return 0;
+        }
+        let len = buf.len().min(PAGE_SIZE - offset);
+        // SAFETY: `offset + len` is within the page, and writers of the page
+        // are serialized with readers by their callers.
+        unsafe { core::ptr::copy_nonoverlapping(self.as_ptr().add(offset), buf.as_mut_ptr(), len) };
+        len
+    }
+
+    /// Writes `buf` into the page at `offset`, and returns the number of
+    /// bytes written, which is 0 if `offset` is beyond the page.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may read or write the page at the same time, except
+    /// through the mappings of it.
+    pub unsafe fn write(&self, offset: usize, buf: &[u8]) -> usize {
+        if offset >= PAGE_SIZE {
+            return 0;
+        }
+        let len = buf.len().min(PAGE_SIZE - offset);
+        // SAFETY: `offset + len` is within the page, and the access is
+        // exclusive as required by the caller.
+        unsafe { core::ptr::copy_nonoverlapping(buf.as_ptr(), self.as_ptr().add(offset), len) };
+        len
+    }
+
+    /// Copies the whole content of `src` into the page.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may read or write the page, or write `src`, at the
+    /// same time, except through the mappings of them.
+    pub unsafe fn copy_from(&self, src: &FilePage) {
+        // SAFETY: both pages are `PAGE_SIZE` bytes, `copy` allows `src` to be
+        // this page, and the access is exclusive as required by the caller.
+        unsafe { core::ptr::copy(src.as_ptr(), self.as_ptr(), PAGE_SIZE) };
+    }
+
+    /// Fills the page with zeros from `offset` to the end.
+    ///
+    /// # Safety
+    ///
+    /// The same as [`write()`](Self::write).
+    pub unsafe fn zero_from(&self, offset: usize) {
+        // SAFETY: guaranteed by the caller.
+        unsafe { self.zero(offset, PAGE_SIZE) };
+    }
+
+    /// Fills `len` bytes of the page at `offset` with zeros, stopping at the
+    /// end of the page. Nothing is filled if `offset` is beyond the page.
+    ///
+    /// # Safety
+    ///
+    /// The same as [`write()`](Self::write).
+    pub unsafe fn zero(&self, offset: usize, len: usize) {
+        if offset >= PAGE_SIZE {
+            return;
+        }
+        let len = len.min(PAGE_SIZE - offset);
+        // SAFETY: `offset + len` is within the page, and the access is
+        // exclusive as required by the caller.
+        unsafe { core::ptr::write_bytes(self.as_ptr().add(offset), 0, len) };
+    }
+
+    /// Returns `true` if the page has been written through a mapping and not
+    /// written back yet.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// Marks the page as written through a mapping.
+    pub fn set_dirty(&self) {
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Clears the dirty flag, and returns whether it was set.
+    pub fn clear_dirty(&self) -> bool {
+        self.dirty.swap(false, Ordering::AcqRel)
+    }
+}
+
+impl Default for FilePage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,6 +1,445 @@
 //! Utilities for path manipulation.
+//!
+//! [`Path`] and [`PathBuf`] are the borrowed and owned paths, similar to
+//! those in `std` but always UTF-8 with `/` as the separator. Iterating over
+//! the [`components()`](Path::components) of a path never allocates.
 
+use alloc::borrow::{Borrow, ToOwned};
 use alloc::string::String;
+use core::fmt;
+use core::ops::Deref;
+
+/// A component of a [`Path`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component<'a> {
+    /// The root directory, i.e. the leading `/` of an absolute path.
+    RootDir,
+    /// The current directory `.`, only as the first component of a relative
+    /// path.
+    CurDir,
+    /// The parent directory `..`.
+    ParentDir,
+    /// A normal component, such as `a` and `b` in `a/b`.
+    Normal(&'a str),
+}
+
+impl<'a> Component<'a> {
+    /// Returns the component as a string slice.
+    pub fn as_str(self) -> &'a str {
+        match self {
+            Component::RootDir => "/",
+            Component::CurDir => ".",
+            Component::ParentDir => "..",
+            Component::Normal(name) => name,
+        }
+    }
+}
+
+/// An iterator over the [`Component`]s of a [`Path`], created by
+/// [`Path::components`].
+///
+/// Repeated separators and `.` components are skipped, except a leading `.`
+/// of a relative path, which is returned as [`Component::CurDir`].
+#[derive(Debug, Clone)]
+pub struct Components<'a> {
+    rest: &'a str,
+    root: bool,
+    cur_dir: bool,
+}
+
+impl<'a> Components<'a> {
+    fn new(path: &'a str) -> Self {
+        let root = path.starts_with('/');
+        let cur_dir = !root && (path == "." || path.starts_with("./"));
+        Self {
+            rest: path,
+            root,
+            cur_dir,
+        }
+    }
+
+    /// Returns the remaining part of the path, without trailing separators.
+    pub fn as_path(&self) -> &'a Path {
+        let trimmed = self.rest.trim_end_matches('/');
+        if trimmed.is_empty() && self.root {
+            Path::new(&self.rest[..1])
+        } else {
+            Path::new(trimmed)
+        }
+    }
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = Component<'a>;
+
+    fn next(&mut self) -> Option<Component<'a>> {
+        if self.root {
+            self.root = false;
+            self.rest = self.rest.trim_start_matches('/');
+            return Some(Component::RootDir);
+        }
+        if self.cur_dir {
+            self.cur_dir = false;
+            self.rest = &self.rest[1..];
+            return Some(Component::CurDir);
+        }
+        loop {
+            self.rest = self.rest.trim_start_matches('/');
+            if self.rest.is_empty() {
+                return None;
+            }
+            let (name, rest) = self.rest.split_once('/').unwrap_or((self.rest, ""));
+            self.rest = rest;
+            match name {
+                "." => continue,
+                ".." => return Some(Component::ParentDir),
+                _ => return Some(Component::Normal(name)),
+            }
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for Components<'a> {
+    fn next_back(&mut self) -> Option<Component<'a>> {
+        loop {
+            let trimmed = self.rest.trim_end_matches('/');
+            if trimmed.is_empty() {
+                if self.root {
+                    self.root = false;
+                    self.rest = "";
+                    return Some(Component::RootDir);
+                }
+                self.rest = "";
+                return None;
+            }
+            let (rest, name) = trimmed.rsplit_once('/').unwrap_or(("", trimmed));
+            // keep the separator before the name, which may be the root
+            self.rest = &trimmed[..rest.len() + (trimmed.len() > name.len()) as usize];
+            match name {
+                "." if self.cur_dir && rest.is_empty() => {
+                    self.cur_dir = false;
+                    self.rest = "";
+                    return Some(Component::CurDir);
+                }
+                "." => continue,
+                ".." => return Some(Component::ParentDir),
+                _ => return Some(Component::Normal(name)),
+            }
+        }
+    }
+}
+
+/// A borrowed path, which is a string slice with `/` as the separator.
+///
+/// # Examples
+///
+/// ```
+/// use axfs_vfs::path::Path;
+///
+/// let path = Path::new("/usr/lib/libc.so.6");
+/// assert!(path.is_absolute());
+/// assert_eq!(path.parent(), Some(Path::new("/usr/lib")));
+/// assert_eq!(path.file_name(), Some("libc.so.6"));
+/// assert_eq!(path.extension(), Some("6"));
+/// assert_eq!(path.strip_prefix("/usr"), Some(Path::new("lib/libc.so.6")));
+/// assert_eq!(Path::new("/usr").join("bin"), "/usr/bin");
+/// ```
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Path {
+    inner: str,
+}
+
+impl Path {
+    /// Wraps a string slice as a path.
+    pub fn new<S: AsRef<str> + ?Sized>(s: &S) -> &Path {
+        // SAFETY: `Path` is a transparent wrapper of `str`.
+        unsafe { &*(s.as_ref() as *const str as *const Path) }
+    }
+
+    /// Returns the path as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
+    /// Returns `true` if the path is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns `true` if the path starts with the root `/`.
+    pub fn is_absolute(&self) -> bool {
+        self.inner.starts_with('/')
+    }
+
+    /// Returns `true` if the path is not absolute.
+    pub fn is_relative(&self) -> bool {
+        !self.is_absolute()
+    }
+
+    /// Returns an iterator over the components of the path.
+    pub fn components(&self) -> Components<'_> {
+        Components::new(&self.inner)
+    }
+
+    /// Returns the path without its last component, or `None` if the path
+    /// is the root or empty.
+    pub fn parent(&self) -> Option<&Path> {
+        let mut comps = self.components();
+        match comps.next_back()? {
+            Component::RootDir => None,
+            _ => Some(comps.as_path()),
+        }
+    }
+
+    /// Returns the last component of the path if it is a normal one.
+    pub fn file_name(&self) -> Option<&str> {
+        match self.components().next_back()? {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`file_name()`](Self::file_name) without the extension.
+    pub fn file_stem(&self) -> Option<&str> {
+        let name = self.file_name()?;
+        match name.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => Some(stem),
+            _ => Some(name),
+        }
+    }
+
+    /// Returns the extension of the [`file_name()`](Self::file_name), which
+    /// is the part after the last `.`, if any.
+    ///
+    /// A leading `.` of the name does not start an extension.
+    pub fn extension(&self) -> Option<&str> {
+        match self.file_name()?.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => Some(ext),
+            _ => None,
+        }
+    }
+
+    /// Returns the path with `base` removed from its start, comparing by
+    /// components, or `None` if the path does not start with `base`.
+    pub fn strip_prefix<P: AsRef<Path> + ?Sized>(&self, base: &P) -> Option<&Path> {
+        let mut comps = self.components();
+        for comp in base.as_ref().components() {
+            if comps.next() != Some(comp) {
+                return None;
+            }
+        }
+        Some(comps.as_path())
+    }
+
+    /// Returns `true` if the path starts with `base`, comparing by
+    /// components.
+    pub fn starts_with<P: AsRef<Path> + ?Sized>(&self, base: &P) -> bool {
+        self.strip_prefix(base).is_some()
+    }
+
+    /// Splits the first component from the rest of the path, skipping the
+    /// leading separators.
+    ///
+    /// The rest is `None` if there is no separator after the first
+    /// component, so that a trailing separator can be told apart. Unlike
+    /// [`components()`](Self::components), `.` components are not skipped.
+    pub fn split_first(&self) -> (&str, Option<&Path>) {
+        let trimmed = self.inner.trim_start_matches('/');
+        match trimmed.split_once('/') {
+            Some((name, rest)) => (name, Some(Path::new(rest))),
+            None => (trimmed, None),
+        }
+    }
+
+    /// Creates an owned path with `path` appended, as
+    /// [`PathBuf::push`] does.
+    pub fn join<P: AsRef<Path> + ?Sized>(&self, path: &P) -> PathBuf {
+        let mut buf = self.to_path_buf();
+        buf.push(path);
+        buf
+    }
+
+    /// Copies the path to a [`PathBuf`].
+    pub fn to_path_buf(&self) -> PathBuf {
+        PathBuf {
+            inner: self.inner.into(),
+        }
+    }
+}
+
+/// Paths are equal if they have the same components.
+impl PartialEq for Path {
+    fn eq(&self, other: &Path) -> bool {
+        self.components().eq(other.components())
+    }
+}
+
+impl Eq for Path {}
+
+impl PartialEq<str> for Path {
+    fn eq(&self, other: &str) -> bool {
+        *self == *Path::new(other)
+    }
+}
+
+impl PartialEq<&str> for Path {
+    fn eq(&self, other: &&str) -> bool {
+        *self == *Path::new(*other)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.inner)
+    }
+}
+
+impl AsRef<Path> for Path {
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
+impl AsRef<Path> for str {
+    fn as_ref(&self) -> &Path {
+        Path::new(self)
+    }
+}
+
+impl AsRef<Path> for String {
+    fn as_ref(&self) -> &Path {
+        Path::new(self)
+    }
+}
+
+impl AsRef<str> for Path {
+    fn as_ref(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl ToOwned for Path {
+    type Owned = PathBuf;
+
+    fn to_owned(&self) -> PathBuf {
+        self.to_path_buf()
+    }
+}
+
+/// An owned path, which is a [`String`] with `/` as the separator.
+#[derive(Debug, Clone, Default)]
+pub struct PathBuf {
+    inner: String,
+}
+
+impl PathBuf {
+    /// Creates an empty path.
+    pub const fn new() -> Self {
+        Self {
+            inner: String::new(),
+        }
+    }
+
+    /// Returns the borrowed path.
+    pub fn as_path(&self) -> &Path {
+        Path::new(&self.inner)
+    }
+
+    /// Converts the path into a [`String`].
+    pub fn into_string(self) -> String {
+        self.inner
+    }
+
+    /// Appends `path` to the path, adding a separator if needed.
+    ///
+    /// If `path` is absolute, it replaces the whole path.
+    pub fn push<P: AsRef<Path> + ?Sized>(&mut self, path: &P) {
+        let path = path.as_ref();
+        if path.is_absolute() {
+            self.inner.clear();
+        } else if !self.inner.is_empty() && !self.inner.ends_with('/') {
+            self.inner.push('/');
+        }
+        self.inner.push_str(path.as_str());
+    }
+
+    /// Truncates the path to its [`parent()`](Path::parent), and returns
+    /// `false` if there is no parent.
+    pub fn pop(&mut self) -> bool {
+        match self.as_path().parent().map(|parent| parent.inner.len()) {
+            Some(len) => {
+                self.inner.truncate(len);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Deref for PathBuf {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl Borrow<Path> for PathBuf {
+    fn borrow(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl AsRef<Path> for PathBuf {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl PartialEq for PathBuf {
+    fn eq(&self, other: &PathBuf) -> bool {
+        self.as_path() == other.as_path()
+    }
+}
+
+impl Eq for PathBuf {}
+
+impl PartialEq<str> for PathBuf {
+    fn eq(&self, other: &str) -> bool {
+        self.as_path() == other
+    }
+}
+
+impl PartialEq<&str> for PathBuf {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_path() == *other
+    }
+}
+
+impl fmt::Display for PathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.inner)
+    }
+}
+
+impl From<String> for PathBuf {
+    fn from(inner: String) -> Self {
+        Self { inner }
+    }
+}
+
+impl From<&str> for PathBuf {
+    fn from(s: &str) -> Self {
+        Self { inner: s.into() }
+    }
+}
+
+impl From<PathBuf> for String {
+    fn from(path: PathBuf) -> Self {
+        path.inner
+    }
+}
 
 /// Returns the canonical form of the path with all intermediate components
 /// normalized.
@@ -94,4 +533,93 @@ mod tests {
         assert_eq!(canonicalize("/bleh/bar/../../foo/.."), "/");
         assert_eq!(canonicalize("/bleh/bar/../../foo/../meh"), "/meh");
     }
+
+    #[test]
+    fn test_path_components() {
+        use alloc::vec::Vec;
+        use Component::*;
+
+        let comps = |path| Path::new(path).components().collect::<Vec<_>>();
+        let rev = |path| Path::new(path).components().rev().collect::<Vec<_>>();
+        assert_eq!(comps(""), []);
+        assert_eq!(comps("/"), [RootDir]);
+        assert_eq!(
+            comps("//a/./b//../c/"),
+            [RootDir, Normal("a"), Normal("b"), ParentDir, Normal("c")]
+        );
+        assert_eq!(comps("./a/."), [CurDir, Normal("a")]);
+        assert_eq!(comps("a/./b"), [Normal("a"), Normal("b")]);
+        assert_eq!(comps(".."), [ParentDir]);
+        assert_eq!(
+            rev("//a/./b//../c/"),
+            [Normal("c"), ParentDir, Normal("b"), Normal("a"), RootDir]
+        );
+        assert_eq!(rev("./././a"), [Normal("a"), CurDir]);
+        assert_eq!(rev("/."), [RootDir]);
+
+        let mut comps = Path::new("/a/b/c").components();
+        assert_eq!(comps.next(), Some(RootDir));
+        assert_eq!(comps.next_back(), Some(Normal("c")));
+        assert_eq!(comps.as_path(), "a/b");
+        assert_eq!(comps.next(), Some(Normal("a")));
+        assert_eq!(comps.next_back(), Some(Normal("b")));
+        assert_eq!(comps.next(), None);
+        assert_eq!(comps.next_back(), None);
+    }
+
+    #[test]
+    fn test_path_methods() {
+        let path = Path::new("/a/b.tar.gz/");
+        assert!(path.is_absolute());
+        assert!(Path::new("a").is_relative());
+        assert_eq!(path.parent().unwrap().as_str(), "/a");
+        assert_eq!(Path::new("/a").parent().unwrap().as_str(), "/");
+        assert_eq!(Path::new("a").parent().unwrap().as_str(), "");
+        assert_eq!(Path::new("/").parent(), None);
+        assert_eq!(Path::new("").parent(), None);
+        assert_eq!(path.file_name(), Some("b.tar.gz"));
+        assert_eq!(Path::new("a/..").file_name(), None);
+        assert_eq!(path.file_stem(), Some("b.tar"));
+        assert_eq!(path.extension(), Some("gz"));
+        assert_eq!(Path::new(".bashrc").extension(), None);
+        assert_eq!(Path::new(".bashrc").file_stem(), Some(".bashrc"));
+        assert_eq!(Path::new("a.").extension(), Some(""));
+
+        assert_eq!(path.strip_prefix("/a").unwrap().as_str(), "b.tar.gz");
+        assert_eq!(path.strip_prefix("//a/./").unwrap().as_str(), "b.tar.gz");
+        assert_eq!(path.strip_prefix(path).unwrap().as_str(), "");
+        assert_eq!(path.strip_prefix("a"), None);
+        assert_eq!(path.strip_prefix("/a/b"), None);
+        assert!(path.starts_with("/"));
+        assert_eq!(Path::new("a//b/"), Path::new("a/b"));
+        assert_ne!(Path::new("/a"), Path::new("a"));
+
+        assert_eq!(Path::new("a").split_first(), ("a", None));
+        let (name, rest) = Path::new("//a/./b").split_first();
+        assert_eq!((name, rest.unwrap().as_str()), ("a", "./b"));
+        assert_eq!(Path::new("a/").split_first().1.unwrap().as_str(), "");
+    }
+
+    #[test]
+    fn test_path_buf() {
+        let mut buf = Path::new("/a").join("b");
+        assert_eq!(buf.as_str(), "/a/b");
+        buf.push("c/");
+        buf.push("d");
+        assert_eq!(buf.as_str(), "/a/b/c/d");
+        assert!(buf.pop());
+        assert_eq!(buf.as_str(), "/a/b/c");
+        buf.push("/e");
+        assert_eq!(buf, "/e");
+        assert!(buf.pop());
+        assert_eq!(buf.as_str(), "/");
+        assert!(!buf.pop());
+
+        let mut buf = PathBuf::new();
+        buf.push("a");
+        assert_eq!(buf.file_name(), Some("a"));
+        assert!(buf.pop());
+        assert!(buf.is_empty());
+        assert_eq!(String::from(PathBuf::from("x/y")), "x/y");
+    }
 }
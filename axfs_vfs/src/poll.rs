@@ -0,0 +1,87 @@
+use alloc::vec::Vec;
+use core::task::Waker;
+
+use spin::Mutex;
+
+bitflags::bitflags! {
+    /// I/O readiness events of a node, same as the `POLL*` constants in
+    /// Linux.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PollEvents: u32 {
+        /// There is data to read.
+        const IN = 0x0001;
+        /// There is urgent data to read.
+        const PRI = 0x0002;
+        /// Writing is now possible.
+        const OUT = 0x0004;
+        /// Error condition, always reported.
+        const ERR = 0x0008;
+        /// Hang up, always reported.
+        const HUP = 0x0010;
+        /// The node is not valid, always reported.
+        const NVAL = 0x0020;
+        /// The peer closed its writing end.
+        const RDHUP = 0x2000;
+    }
+}
+
+impl PollEvents {
+    /// Events that are always reported, even if not requested.
+    pub const ALWAYS: Self = Self::ERR.union(Self::HUP).union(Self::NVAL);
+}
+
+/// A set of wakers waiting for events of a node.
+///
+/// Nodes that can be not ready, such as devices and pipes, keep one of them
+/// to implement [`VfsNodeOps::poll`](crate::VfsNodeOps::poll), and call
+/// [`wake()`](PollSet::wake) when their state changes.
+#[derive(Default)]
+pub struct PollSet {
+    wakers: Mutex<Vec<(PollEvents, Waker)>>,
+}
+
+impl PollSet {
+    /// Creates an empty set.
+    pub const fn new() -> Self {
+        Self {
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers `waker` to be woken when any of `events` happens.
+    ///
+    /// A waker that would wake the same task as a registered one is merged
+    /// into it.
+    pub fn register(&self, events: PollEvents, waker: &Waker) {
+        let events = events | PollEvents::ALWAYS;
+        let mut wakers = self.wakers.lock();
+        match wakers.iter_mut().find(|(_, w)| w.will_wake(waker)) {
+            Some((ev, _)) => *ev |= events,
+            None => wakers.push((events, waker.clone())),
+        }
+    }
+
+    /// Wakes and removes all wakers waiting for any of `events`.
+    ///
+    /// Returns the number of woken wakers.
+    pub fn wake(&self, events: PollEvents) -> usize {
+        let mut woken = Vec::new();
+        self.wakers.lock().retain(|(ev, waker)| {
+            if ev.intersects(events) {
+                woken.push(waker.clone());
+                false
+            } else {
+                true
+            }
+        });
+        // wake outside the lock, the woken task may register again
+        let count = woken.len();
+        woken.into_iter().for_each(Waker::wake);
+        count
+    }
+
+    /// Returns `true` if no waker is registered.
+    pub fn is_empty(&self) -> bool {
+        self.wakers.lock().is_empty()
+    }
+}
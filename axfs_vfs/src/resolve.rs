@@ -0,0 +1,221 @@
+use alloc::{string::String, sync::Arc, vec};
+
+use crate::{AccessMode, Credentials, VfsError, VfsNodeRef, VfsResult, NAME_MAX};
+
+/// The default maximum number of symbolic links followed in a path walk, the
+/// same as `MAXSYMLINKS` in Linux.
+pub const SYMLOOP_MAX: usize = 40;
+
+/// A function that looks up a single name in a directory, without following
+/// the symbolic link it refers to.
+///
+/// It is also called with `..` to find the parent of a directory, except for
+/// the root of the walk.
+pub type LookupFn<'a> = dyn Fn(&VfsNodeRef, &str) -> VfsResult<VfsNodeRef> + 'a;
+
+/// Resolves paths by walking through nodes one component at a time.
+///
+/// The walk is iterative, the target of a symbolic link is spliced into the
+/// remaining path instead of being resolved recursively, so deep paths and
+/// nested links do not consume the stack. At most [`SYMLOOP_MAX`] links are
+/// followed by default, more result in [`VfsError::FilesystemLoop`].
+///
+/// `.` is handled by the resolver, and `..` of the root stays at the root.
+/// Like other names, they can only be resolved in a directory. Other names,
+/// and `..` elsewhere, are looked up with
+/// [`lookup_nofollow()`](crate::VfsNodeOps::lookup_nofollow) and
+/// [`parent()`](crate::VfsNodeOps::parent) of the directory, unless another
+/// function is given with [`with_lookup()`](Self::with_lookup).
+pub struct PathResolver<'a> {
+    root: Option<VfsNodeRef>,
+    cred: Option<&'a Credentials>,
+    max_symlinks: usize,
+    lookup: &'a LookupFn<'a>,
+}
+
+impl<'a> PathResolver<'a> {
+    /// Creates a resolver with the default settings.
+    ///
+    /// Absolute paths and symbolic link targets start from the topmost
+    /// ancestor of the directory being walked.
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            cred: None,
+            max_symlinks: SYMLOOP_MAX,
+            lookup: &lookup_child,
+        }
+    }
+
+    /// Sets the root directory, where absolute paths start, and which `..`
+    /// does not go beyond.
+    pub fn with_root(mut self, root: VfsNodeRef) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Checks that `cred` can search every directory walked through.
+    pub fn with_credentials(mut self, cred: &'a Credentials) -> Self {
+        self.cred = Some(cred);
+        self
+    }
+
+    /// Sets the maximum number of symbolic links followed in a walk.
+    pub fn with_max_symlinks(mut self, max_symlinks: usize) -> Self {
+        self.max_symlinks = max_symlinks;
+        self
+    }
+
+    /// Sets the function used to look up a single name in a directory.
+    ///
+    /// Filesystems whose
+    /// [`lookup_nofollow()`](crate::VfsNodeOps::lookup_nofollow) is built on
+    /// the resolver should look up their own directories directly.
+    pub fn with_lookup(mut self, lookup: &'a LookupFn<'a>) -> Self {
+        self.lookup = lookup;
+        self
+    }
+
+    /// Resolves `path` relative to `start`, or from the root if it is
+    /// absolute.
+    ///
+    /// The symbolic link at the last component is followed if `follow` is
+    /// `true` or the path ends with a slash, in which case the node must also
+    /// be a directory.
+    pub fn resolve(&self, start: &VfsNodeRef, path: &str, follow: bool) -> VfsResult<VfsNodeRef> {
+        let mut dir = if path.starts_with('/') {
+            self.root_of(start)
+        } else {
+            start.clone()
+        };
+        // the path with the targets of the followed links spliced in
+        let mut expanded: Option<String> = None;
+        let mut pos = 0;
+        let mut links = 0;
+        loop {
+            let rest = expanded.as_deref().unwrap_or(path)[pos..].trim_start_matches('/');
+            if rest.is_empty() {
+                return Ok(dir);
+            }
+            let (name, tail) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+            let is_last = tail.trim_start_matches('/').is_empty();
+            self.check_search(&dir)?;
+            let node = match name {
+                "." => dir.clone(),
+                ".." => self.parent_of(&dir)?,
+                _ if name.len() > NAME_MAX => return Err(VfsError::NameTooLong),
+                _ => (self.lookup)(&dir, name)?,
+            };
+
+            // a trailing slash also forces the last component to be followed
+            let attr = if !is_last || follow || !tail.is_empty() {
+                Some(node.get_attr()?)
+            } else {
+                None
+            };
+            if let Some(attr) = attr.as_ref().filter(|attr| attr.is_symlink()) {
+                links += 1;
+                if links > self.max_symlinks {
+                    return Err(VfsError::FilesystemLoop);
+                }
+                let mut target = read_link(&node, attr.size())?;
+                if target.is_empty() {
+                    return Err(VfsError::NotFound);
+                }
+                if target.starts_with('/') {
+                    dir = self.root_of(&dir);
+                }
+                target.push_str(tail);
+                expanded = Some(target);
+                pos = 0;
+                continue;
+            }
+
+            if is_last {
+                if !tail.is_empty() && !attr.is_some_and(|attr| attr.is_dir()) {
+                    return Err(VfsError::NotADirectory);
+                }
+                return Ok(node);
+            }
+            pos = expanded.as_deref().unwrap_or(path).len() - tail.len();
+            dir = node;
+        }
+    }
+
+    /// Resolves the directory containing the last component of `path`, and
+    /// returns it with the name of the last component, for creating or
+    /// removing it.
+    ///
+    /// Trailing slashes are ignored. The name is empty if `path` has no
+    /// component, and may be `.` or `..`, which callers should reject or
+    /// treat as an existing node.
+    pub fn resolve_parent<'p>(
+        &self,
+        start: &VfsNodeRef,
+        path: &'p str,
+    ) -> VfsResult<(VfsNodeRef, &'p str)> {
+        let trimmed = path.trim_end_matches('/');
+        let (dir_path, name) = match trimmed.rsplit_once('/') {
+            Some(("", name)) => ("/", name),
+            Some((dir_path, name)) => (dir_path, name),
+            None if path.starts_with('/') => ("/", ""),
+            None => ("", trimmed),
+        };
+        Ok((self.resolve(start, dir_path, true)?, name))
+    }
+
+    /// Checks that `dir` is a directory, which the credentials can search if
+    /// there are any.
+    fn check_search(&self, dir: &VfsNodeRef) -> VfsResult {
+        let attr = dir.get_attr()?;
+        if !attr.is_dir() {
+            return Err(VfsError::NotADirectory);
+        }
+        match self.cred {
+            Some(cred) => cred.check_access(&attr, AccessMode::EXECUTE),
+            None => Ok(()),
+        }
+    }
+
+    fn root_of(&self, node: &VfsNodeRef) -> VfsNodeRef {
+        if let Some(root) = &self.root {
+            return root.clone();
+        }
+        let mut node = node.clone();
+        while let Some(parent) = node.parent() {
+            node = parent;
+        }
+        node
+    }
+
+    fn parent_of(&self, dir: &VfsNodeRef) -> VfsResult<VfsNodeRef> {
+        if self
+            .root
+            .as_ref()
+            .is_some_and(|root| Arc::ptr_eq(root, dir))
+        {
+            return Ok(dir.clone());
+        }
+        (self.lookup)(dir, "..")
+    }
+}
+
+impl Default for PathResolver<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lookup_child(dir: &VfsNodeRef, name: &str) -> VfsResult<VfsNodeRef> {
+    if name == ".." {
+        return Ok(dir.parent().unwrap_or_else(|| dir.clone()));
+    }
+    dir.clone().lookup_nofollow(name)
+}
+
+fn read_link(node: &VfsNodeRef, size: u64) -> VfsResult<String> {
+    let mut buf = vec![0; size as usize];
+    let len = node.readlink(&mut buf)?;
+    buf.truncate(len);
+    String::from_utf8(buf).map_err(|_| VfsError::InvalidData)
+}
@@ -1,13 +1,72 @@
-/// Filesystem attributes.
+use alloc::string::String;
+use core::time::Duration;
+
+/// Filesystem attributes, returned by [`VfsOps::statfs`].
 ///
-/// Currently not used.
+/// [`VfsOps::statfs`]: crate::VfsOps::statfs
 #[non_exhaustive]
-pub struct FileSystemInfo;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSystemInfo {
+    /// Magic number of the filesystem type, such as `0x858458f6` for ramfs.
+    pub fs_type: u64,
+    /// Size of a block, in bytes.
+    pub block_size: u64,
+    /// Total number of blocks.
+    pub blocks: u64,
+    /// Number of free blocks.
+    pub blocks_free: u64,
+    /// Number of free blocks available to unprivileged users.
+    pub blocks_available: u64,
+    /// Total number of inodes.
+    pub files: u64,
+    /// Number of free inodes.
+    pub files_free: u64,
+    /// Filesystem ID, usually the device ID.
+    pub fsid: u64,
+    /// Maximum length of file names.
+    pub name_max: u64,
+    /// Mount flags.
+    ///
+    /// Filesystems report the flags inherent to them, and a mount table adds
+    /// the flags the filesystem is mounted with.
+    pub flags: VfsMountFlags,
+}
+
+bitflags::bitflags! {
+    /// Mount flags of a filesystem, reported in [`FileSystemInfo`].
+    ///
+    /// The values are the same as the `ST_*` constants of `statvfs`.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct VfsMountFlags: u64 {
+        /// Read-only filesystem.
+        const RDONLY = 0x1;
+        /// Set-user-ID and set-group-ID bits are ignored.
+        const NOSUID = 0x2;
+        /// Device files cannot be accessed.
+        const NODEV = 0x4;
+        /// Programs cannot be executed.
+        const NOEXEC = 0x8;
+        /// Writes are synced immediately.
+        const SYNCHRONOUS = 0x10;
+        /// Mandatory locking is permitted.
+        const MANDLOCK = 0x40;
+        /// Access times are not updated.
+        const NOATIME = 0x400;
+        /// Directory access times are not updated.
+        const NODIRATIME = 0x800;
+        /// Access times are updated relative to modification times.
+        const RELATIME = 0x1000;
+    }
+}
 
 /// Node (file/directory) attributes.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub struct VfsNodeAttr {
+    /// ID of the device containing the node.
+    dev: u64,
+    /// Inode number.
+    ino: u64,
     /// File permission mode.
     mode: VfsNodePerm,
     /// File type.
@@ -16,6 +75,22 @@ pub struct VfsNodeAttr {
     size: u64,
     /// Number of 512B blocks allocated.
     blocks: u64,
+    /// Number of hard links.
+    nlink: u64,
+    /// User ID of the owner.
+    uid: u32,
+    /// Group ID of the owner.
+    gid: u32,
+    /// Device ID, if the node is a character or block device.
+    rdev: u64,
+    /// Time of last access.
+    atime: Duration,
+    /// Time of last modification.
+    mtime: Duration,
+    /// Time of last status change.
+    ctime: Duration,
+    /// Time of creation.
+    btime: Duration,
 }
 
 bitflags::bitflags! {
@@ -45,6 +120,60 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// Which fields of [`VfsNodeAttr`] to change in
+    /// [`VfsNodeOps::set_attr`](crate::VfsNodeOps::set_attr).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct VfsAttrMask: u32 {
+        /// Change the permission mode (`chmod`).
+        const MODE = 1 << 0;
+        /// Change the user ID of the owner (`chown`).
+        const UID = 1 << 1;
+        /// Change the group ID of the owner (`chown`).
+        const GID = 1 << 2;
+        /// Change the time of last access (`utimes`).
+        const ATIME = 1 << 3;
+        /// Change the time of last modification (`utimes`).
+        const MTIME = 1 << 4;
+    }
+}
+
+bitflags::bitflags! {
+    /// Modes of [`VfsNodeOps::fallocate`](crate::VfsNodeOps::fallocate), same
+    /// as the `FALLOC_FL_*` constants in Linux.
+    ///
+    /// The empty mode allocates the range, and extends the file if the range
+    /// is beyond the end of file.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct FallocateMode: u32 {
+        /// Do not change the file size, even if the range is beyond the end
+        /// of file.
+        const KEEP_SIZE = 0x01;
+        /// Deallocate the range, which then reads as zeros. Must be used
+        /// with [`KEEP_SIZE`](Self::KEEP_SIZE).
+        const PUNCH_HOLE = 0x02;
+        /// Remove the range from the file, shifting the data after it down.
+        const COLLAPSE_RANGE = 0x08;
+        /// Zero the range, and keep it allocated.
+        const ZERO_RANGE = 0x10;
+        /// Insert a hole at the range, shifting the data after it up.
+        const INSERT_RANGE = 0x20;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags of [`VfsNodeOps::rename_with_flags`](crate::VfsNodeOps::rename_with_flags),
+    /// same as the `RENAME_*` constants of `renameat2` in Linux.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RenameFlags: u32 {
+        /// Fail with [`VfsError::AlreadyExists`](crate::VfsError::AlreadyExists)
+        /// if the target exists.
+        const NOREPLACE = 1 << 0;
+        /// Exchange the source and the target, which must both exist.
+        const EXCHANGE = 1 << 1;
+    }
+}
+
 /// Node (file/directory) type.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -65,10 +194,18 @@ pub enum VfsNodeType {
     Socket = 0o14,
 }
 
+/// Maximum length of a file name, in bytes.
+pub const NAME_MAX: usize = 255;
+
 /// Directory entry.
+///
+/// The name is stored in its own allocation of the needed length, so entries
+/// stay small however long names can be, up to [`NAME_MAX`] bytes.
 pub struct VfsDirEntry {
+    d_ino: u64,
+    d_off: u64,
     d_type: VfsNodeType,
-    d_name: [u8; 63],
+    d_name: String,
 }
 
 impl VfsNodePerm {
@@ -87,6 +224,14 @@ impl VfsNodePerm {
         Self::from_bits_truncate(0o755)
     }
 
+    /// Returns the default permission for a symbolic link.
+    ///
+    /// The default permission is `0o777`, the permission of a symbolic link is
+    /// never used.
+    pub const fn default_symlink() -> Self {
+        Self::from_bits_truncate(0o777)
+    }
+
     /// Returns the underlying raw `st_mode` bits that contain the standard
     /// Unix permissions for this file.
     pub const fn mode(&self) -> u32 {
@@ -199,36 +344,93 @@ impl VfsNodeType {
 impl VfsNodeAttr {
     /// Creates a new `VfsNodeAttr` with the given permission mode, type, size
     /// and number of blocks.
+    ///
+    /// The device ID and inode number are set to 0, the number of hard links
+    /// is set to 1, the owner is set to root, and all timestamps are set to
+    /// zero.
     pub const fn new(mode: VfsNodePerm, ty: VfsNodeType, size: u64, blocks: u64) -> Self {
         Self {
+            dev: 0,
+            ino: 0,
             mode,
             ty,
             size,
             blocks,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
+            btime: Duration::ZERO,
         }
     }
 
     /// Creates a new `VfsNodeAttr` for a file, with the default file permission.
     pub const fn new_file(size: u64, blocks: u64) -> Self {
         Self {
+            dev: 0,
+            ino: 0,
             mode: VfsNodePerm::default_file(),
             ty: VfsNodeType::File,
             size,
             blocks,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
+            btime: Duration::ZERO,
         }
     }
 
     /// Creates a new `VfsNodeAttr` for a directory, with the default directory
     /// permission.
+    ///
+    /// The number of hard links is set to 2 (for the entry in the parent and
+    /// the `.` entry).
     pub const fn new_dir(size: u64, blocks: u64) -> Self {
         Self {
+            dev: 0,
+            ino: 0,
             mode: VfsNodePerm::default_dir(),
             ty: VfsNodeType::Dir,
             size,
             blocks,
+            nlink: 2,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
+            btime: Duration::ZERO,
         }
     }
 
+    /// Returns the ID of the device containing the node.
+    pub const fn dev(&self) -> u64 {
+        self.dev
+    }
+
+    /// Sets the ID of the device containing the node.
+    pub fn set_dev(&mut self, dev: u64) {
+        self.dev = dev
+    }
+
+    /// Returns the inode number of the node.
+    pub const fn ino(&self) -> u64 {
+        self.ino
+    }
+
+    /// Sets the inode number of the node.
+    pub fn set_ino(&mut self, ino: u64) {
+        self.ino = ino
+    }
+
     /// Returns the size of the node.
     pub const fn size(&self) -> u64 {
         self.size
@@ -239,6 +441,95 @@ impl VfsNodeAttr {
         self.blocks
     }
 
+    /// Returns the number of hard links to the node.
+    pub const fn nlink(&self) -> u64 {
+        self.nlink
+    }
+
+    /// Sets the number of hard links to the node.
+    pub fn set_nlink(&mut self, nlink: u64) {
+        self.nlink = nlink
+    }
+
+    /// Returns the user ID of the owner.
+    pub const fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Sets the user ID of the owner.
+    pub fn set_uid(&mut self, uid: u32) {
+        self.uid = uid
+    }
+
+    /// Returns the group ID of the owner.
+    pub const fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Sets the group ID of the owner.
+    pub fn set_gid(&mut self, gid: u32) {
+        self.gid = gid
+    }
+
+    /// Returns the device ID of the node, if it is a character or block
+    /// device.
+    ///
+    /// The ID can be split into the major and minor numbers with [`major()`]
+    /// and [`minor()`].
+    ///
+    /// [`major()`]: crate::major
+    /// [`minor()`]: crate::minor
+    pub const fn rdev(&self) -> u64 {
+        self.rdev
+    }
+
+    /// Sets the device ID of the node.
+    pub fn set_rdev(&mut self, rdev: u64) {
+        self.rdev = rdev
+    }
+
+    /// Returns the time of last access, as the duration since the Unix epoch.
+    pub const fn atime(&self) -> Duration {
+        self.atime
+    }
+
+    /// Sets the time of last access.
+    pub fn set_atime(&mut self, atime: Duration) {
+        self.atime = atime
+    }
+
+    /// Returns the time of last modification, as the duration since the Unix
+    /// epoch.
+    pub const fn mtime(&self) -> Duration {
+        self.mtime
+    }
+
+    /// Sets the time of last modification.
+    pub fn set_mtime(&mut self, mtime: Duration) {
+        self.mtime = mtime
+    }
+
+    /// Returns the time of last status change, as the duration since the Unix
+    /// epoch.
+    pub const fn ctime(&self) -> Duration {
+        self.ctime
+    }
+
+    /// Sets the time of last status change.
+    pub fn set_ctime(&mut self, ctime: Duration) {
+        self.ctime = ctime
+    }
+
+    /// Returns the creation time, as the duration since the Unix epoch.
+    pub const fn btime(&self) -> Duration {
+        self.btime
+    }
+
+    /// Sets the creation time.
+    pub fn set_btime(&mut self, btime: Duration) {
+        self.btime = btime
+    }
+
     /// Returns the permission of the node.
     pub const fn perm(&self) -> VfsNodePerm {
         self.mode
@@ -263,29 +554,66 @@ impl VfsNodeAttr {
     pub const fn is_dir(&self) -> bool {
         self.ty.is_dir()
     }
+
+    /// Whether the node is a symbolic link.
+    pub const fn is_symlink(&self) -> bool {
+        self.ty.is_symlink()
+    }
 }
 
 impl VfsDirEntry {
     /// Creates an empty `VfsDirEntry`.
     pub const fn default() -> Self {
         Self {
+            d_ino: 0,
+            d_off: 0,
             d_type: VfsNodeType::File,
-            d_name: [0; 63],
+            d_name: String::new(),
         }
     }
 
     /// Creates a new `VfsDirEntry` with the given name and type.
+    ///
+    /// The inode number and the offset are set to 0. Filesystems should
+    /// reject names longer than [`NAME_MAX`] when creating nodes, such names
+    /// are truncated here.
     pub fn new(name: &str, ty: VfsNodeType) -> Self {
-        let mut d_name = [0; 63];
-        if name.len() > d_name.len() {
-            log::warn!(
-                "directory entry name too long: {} > {}",
-                name.len(),
-                d_name.len()
-            );
+        let mut len = name.len();
+        if len > NAME_MAX {
+            log::warn!("directory entry name too long: {len} > {NAME_MAX}");
+            len = (0..=NAME_MAX)
+                .rev()
+                .find(|&i| name.is_char_boundary(i))
+                .unwrap_or(0);
         }
-        d_name[..name.len()].copy_from_slice(name.as_bytes());
-        Self { d_type: ty, d_name }
+        Self {
+            d_ino: 0,
+            d_off: 0,
+            d_type: ty,
+            d_name: String::from(&name[..len]),
+        }
+    }
+
+    /// Returns the inode number of the entry.
+    pub fn ino(&self) -> u64 {
+        self.d_ino
+    }
+
+    /// Sets the inode number of the entry.
+    pub fn set_ino(&mut self, ino: u64) {
+        self.d_ino = ino
+    }
+
+    /// Returns the offset of the entry, which is the cookie passed to
+    /// [`VfsNodeOps::read_dir`](crate::VfsNodeOps::read_dir) to continue
+    /// reading after this entry.
+    pub fn offset(&self) -> u64 {
+        self.d_off
+    }
+
+    /// Sets the offset of the entry.
+    pub fn set_offset(&mut self, off: u64) {
+        self.d_off = off
     }
 
     /// Returns the type of the entry.
@@ -295,11 +623,6 @@ impl VfsDirEntry {
 
     /// Converts the name of the entry to a byte slice.
     pub fn name_as_bytes(&self) -> &[u8] {
-        let len = self
-            .d_name
-            .iter()
-            .position(|&c| c == 0)
-            .unwrap_or(self.d_name.len());
-        &self.d_name[..len]
+        self.d_name.as_bytes()
     }
 }
@@ -0,0 +1,73 @@
+use crate::{VfsError, VfsResult};
+
+/// Maximum length of an extended attribute name, in bytes.
+pub const XATTR_NAME_MAX: usize = 255;
+
+/// Maximum size of an extended attribute value, in bytes.
+pub const XATTR_SIZE_MAX: usize = 65536;
+
+bitflags::bitflags! {
+    /// Flags for [`VfsNodeOps::set_xattr`](crate::VfsNodeOps::set_xattr).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct XattrFlags: u32 {
+        /// Fail if the attribute already exists.
+        const CREATE = 1;
+        /// Fail if the attribute does not exist.
+        const REPLACE = 2;
+    }
+}
+
+/// Namespace of an extended attribute, given by the prefix of its name.
+///
+/// The `trusted.*` namespace is not supported, because the extended attribute
+/// operations take no credentials to hide it from unprivileged processes.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum XattrNamespace {
+    /// `user.*`: arbitrary attributes of regular files and directories.
+    User,
+    /// `security.*`: attributes used by security modules, such as SELinux
+    /// labels.
+    Security,
+    /// `system.*`: attributes used by the kernel, such as ACLs.
+    System,
+}
+
+impl XattrNamespace {
+    /// Splits the extended attribute `name` into its namespace and the name
+    /// without the prefix.
+    ///
+    /// Returns [`VfsError::OperationNotSupported`] for an unknown prefix or
+    /// `trusted`, and [`VfsError::OutOfRange`] if the name is empty or longer
+    /// than [`XATTR_NAME_MAX`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use axfs_vfs::XattrNamespace;
+    ///
+    /// assert_eq!(
+    ///     XattrNamespace::parse("user.mime_type"),
+    ///     Ok((XattrNamespace::User, "mime_type"))
+    /// );
+    /// assert!(XattrNamespace::parse("foo.bar").is_err());
+    /// assert!(XattrNamespace::parse("trusted.md5").is_err());
+    /// ```
+    pub fn parse(name: &str) -> VfsResult<(Self, &str)> {
+        if name.len() > XATTR_NAME_MAX {
+            return Err(VfsError::OutOfRange);
+        }
+        let (prefix, suffix) = name
+            .split_once('.')
+            .ok_or(VfsError::OperationNotSupported)?;
+        let ns = match prefix {
+            "user" => Self::User,
+            "security" => Self::Security,
+            "system" => Self::System,
+            _ => return Err(VfsError::OperationNotSupported),
+        };
+        if suffix.is_empty() {
+            return Err(VfsError::OutOfRange);
+        }
+        Ok((ns, suffix))
+    }
+}